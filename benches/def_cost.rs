@@ -0,0 +1,44 @@
+// -----------------------------------------------------------------------------
+// File: def_cost.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Benchmark showing that defining a procedure in lexical scoping mode costs
+// the same no matter how many names are already defined.
+// Run with: cargo bench --bench def_cost
+// -----------------------------------------------------------------------------
+
+use std::time::Instant;
+
+use ps_interpreter::{Interpreter, ScopeMode};
+
+// How many procedures are timed at each dictionary size.
+const TIMED_DEFS: usize = 2_000;
+
+// Builds a program that defines count procedures named prefix0, prefix1, ...
+fn definitions(prefix: &str, count: usize) -> String
+{
+    (0..count)
+        .map(|i| format!("/{}{} {{ {} 1 add }} def\n", prefix, i, i))
+        .collect()
+}
+
+fn main()
+{
+    println!("{:>12}  {:>14}", "dict size", "ns per def");
+
+    for size in [100, 1_000, 10_000, 50_000]
+    {
+        let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+        // Fill the dictionary first, this part is not timed.
+        postscript_interpreter.interpret(&definitions("lib", size)).unwrap();
+
+        let timed = definitions("app", TIMED_DEFS);
+        let start = Instant::now();
+        postscript_interpreter.interpret(&timed).unwrap();
+        let elapsed = start.elapsed();
+
+        println!("{:>12}  {:>14}", size, elapsed.as_nanos() / TIMED_DEFS as u128);
+    }
+}
@@ -9,17 +9,97 @@
 
 
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::rc::Rc;
 
-use super::dict::Dict;
+use super::dict::PsDict;
 use super::scope::ScopeMode;
-use super::value::Value;
-use super::exec::Interpreter;
+use super::string::PsString;
+use super::tokenizer::{Position, Token, scan_token};
+use super::value::{Access, Procedure, Value, format_real, format_text, format_value};
+use super::error::PsError;
+use super::exec::{Interpreter, ps_error};
+use super::log::LogLevel;
+use super::vm::VirtualMemory;
+
+// Every operator try_builtin knows, with its operands and results in the
+// usual PostScript notation and a short description. Used by the REPL for
+// :help and name completion, so keep it in step with try_builtin.
+pub const OPERATORS: &[(&str, &str, &str)] =
+&[
+    ("add", "num1 num2 add sum", "adds two numbers"),
+    ("sub", "num1 num2 sub difference", "subtracts num2 from num1"),
+    ("mul", "num1 num2 mul product", "multiplies two numbers"),
+    ("div", "num1 num2 div quotient", "divides num1 by num2"),
+    ("mod", "int1 int2 mod remainder", "remainder of dividing int1 by int2"),
+    ("dup", "any dup any any", "duplicates the top value"),
+    ("exch", "any1 any2 exch any2 any1", "swaps the top two values"),
+    ("copy", "any1 .. anyn n copy any1 .. anyn any1 .. anyn", "duplicates the top n values"),
+    ("pop", "any pop -", "discards the top value"),
+    ("count", "any1 .. anyn count any1 .. anyn n", "counts the values on the stack"),
+    ("dict", "int dict dict", "creates an empty dictionary"),
+    ("mark", "- mark mark", "pushes a mark"),
+    ("<<", "- << mark", "starts a dictionary"),
+    (">>", "mark key1 value1 .. keyn valuen >> dict", "makes a dictionary of the pairs above the mark"),
+    ("begin", "dict begin -", "pushes dict onto the dictionary stack"),
+    ("end", "- end -", "pops the top dictionary off the dictionary stack"),
+    ("length", "string|dict length int", "number of bytes or entries"),
+    ("def", "key value def -", "binds key to value in the current dictionary"),
+    ("get", "string index get int | dict key get any", "reads one element"),
+    ("getinterval", "string index count getinterval substring", "substring sharing the same bytes"),
+    ("putinterval", "string1 index string2 putinterval -", "copies string2 into string1 at index"),
+    ("string", "int string string", "creates a string of int zero bytes"),
+    ("put", "string index int put - | dict key value put -", "stores one element"),
+    ("search", "string seek search post match pre true | string false", "finds seek in string"),
+    ("anchorsearch", "string seek anchorsearch post match true | string false", "checks whether string starts with seek"),
+    ("token", "string token post any true | false", "reads the first token of string"),
+    ("readonly", "object readonly object", "removes write access"),
+    ("executeonly", "object executeonly object", "removes read and write access"),
+    ("noaccess", "object noaccess object", "removes all access"),
+    ("rcheck", "object rcheck bool", "tests for read access"),
+    ("wcheck", "object wcheck bool", "tests for write access"),
+    ("type", "any type name", "name of the type of any"),
+    ("cvi", "num|string cvi int", "converts to an integer"),
+    ("cvr", "num|string cvr real", "converts to a real"),
+    ("cvn", "string cvn name", "converts to a name"),
+    ("cvs", "any string cvs substring", "writes the text of any into string"),
+    ("cvrs", "num radix string cvrs substring", "writes num in the given radix into string"),
+    ("eq", "any1 any2 eq bool", "tests for equality"),
+    ("ne", "any1 any2 ne bool", "tests for inequality"),
+    ("gt", "num1|str1 num2|str2 gt bool", "tests for greater than"),
+    ("ge", "num1|str1 num2|str2 ge bool", "tests for greater than or equal"),
+    ("lt", "num1|str1 num2|str2 lt bool", "tests for less than"),
+    ("le", "num1|str1 num2|str2 le bool", "tests for less than or equal"),
+    ("and", "bool1 bool2 and bool3", "logical and"),
+    ("or", "bool1 bool2 or bool3", "logical or"),
+    ("not", "bool not bool", "logical not"),
+    ("print", "string print -", "writes the bytes of string"),
+    ("=", "any = -", "writes the text of any and a newline"),
+    ("==", "any == -", "writes any as PostScript source and a newline"),
+    ("stack", "any1 .. anyn stack any1 .. anyn", "writes every value on the stack with ="),
+    ("pstack", "any1 .. anyn pstack any1 .. anyn", "writes every value on the stack with =="),
+    ("exec", "any exec -", "runs a procedure or executable name, pushes anything else"),
+    ("if", "bool proc if -", "runs proc if bool is true"),
+    ("ifelse", "bool proc1 proc2 ifelse -", "runs proc1 if bool is true, else proc2"),
+    ("repeat", "int proc repeat -", "runs proc int times"),
+    ("for", "initial increment limit proc for -", "runs proc for each value from initial to limit"),
+    ("currentglobal", "- currentglobal bool", "true if new objects go in global VM"),
+    ("setglobal", "bool setglobal -", "chooses global or local VM for new objects"),
+    ("globaldict", "- globaldict dict", "pushes the dictionary kept in global VM"),
+    ("save", "- save save", "starts recording changes to virtual memory"),
+    ("restore", "save restore -", "returns virtual memory to a snapshot"),
+    ("currentscoping", "- currentscoping name", "/lexical or /dynamic, the mode for top level code"),
+    ("setscoping", "name setscoping -", "chooses /lexical or /dynamic scoping for top level code"),
+    ("lexical", "proc lexical proc", "makes proc capture its environment"),
+    ("dynamic", "proc dynamic proc", "makes proc look names up when it runs"),
+    ("settrace", "bool settrace -", "logs every token executed and the stack around it"),
+    ("clear", "any1 .. anyn clear -", "empties the operand stack"),
+];
 
 impl Interpreter
 {
     // Method that runs built-in executions. 
-    pub fn try_builtin(&mut self, name: &str) -> Result<bool, String>
+    pub fn try_builtin(&mut self, name: &str) -> Result<bool, PsError>
     {
         match name
         {
@@ -37,7 +117,7 @@ impl Interpreter
                 {
                     (Value::Int(x), Value::Int(y)) => Value::Int(x + y),
                     (Value::Real(x), Value::Real(y)) => Value::Real(x + y),
-                    _ => return Err("Type error in add".to_string()),
+                    _ => return Err(ps_error("typecheck", "add")),
                 });
 
                 Ok(true)
@@ -57,7 +137,7 @@ impl Interpreter
                 {
                     (Value::Int(x), Value::Int(y)) => Value::Int(x - y),
                     (Value::Real(x), Value::Real(y)) => Value::Real(x - y),
-                    _ => return Err("Type error in sub".to_string()),
+                    _ => return Err(ps_error("typecheck", "sub")),
                 });
 
                 Ok(true)
@@ -77,7 +157,7 @@ impl Interpreter
                 {
                     (Value::Int(x), Value::Int(y)) => Value::Int(x * y),
                     (Value::Real(x), Value::Real(y)) => Value::Real(x * y),
-                    _ => return Err("Type error in add".to_string()),
+                    _ => return Err(ps_error("typecheck", "mul")),
                 });
 
                 Ok(true)
@@ -95,10 +175,10 @@ impl Interpreter
                 // Push the result of subtracting a / b to the stack.
                 self.push(match (a, b)
                 {
-                    (Value::Int(1), Value::Int(0)) => return Err("Dividing by zero will not compute".to_string()),
+                    (Value::Int(_), Value::Int(0)) => return Err(ps_error("undefinedresult", "div")),
                     (Value::Int(x), Value::Int(y)) => Value::Int(x / y),
                     (Value::Real(x), Value::Real(y)) => Value::Real(x / y),
-                    _ => return Err("Type error in sub".to_string()),
+                    _ => return Err(ps_error("typecheck", "div")),
                 });
 
                 Ok(true)
@@ -116,9 +196,9 @@ impl Interpreter
                 // Push the result of subtracting a % b to the stack.
                 self.push(match (a, b)
                 {
-                    (Value::Int(_x), Value::Int(0)) => return Err("Modulus by zero will not compute".to_string()),
+                    (Value::Int(_), Value::Int(0)) => return Err(ps_error("undefinedresult", "mod")),
                     (Value::Int(x), Value::Int(y)) => Value::Int(x % y),
-                    _ => return Err("Type error in sub".to_string()),
+                    _ => return Err(ps_error("typecheck", "mod")),
                 });
 
                 Ok(true)
@@ -127,7 +207,7 @@ impl Interpreter
             // Duplicates the top of the Operand Stack
             "dup" =>
             {
-                let top = self.peek().ok_or("Operand stack underflow")?.clone();
+                let top = self.peek().ok_or_else(|| ps_error("stackunderflow", "dup"))?.clone();
 
                 self.push(top);
 
@@ -158,14 +238,14 @@ impl Interpreter
                 let n = match count_val 
                 {
                     Value::Int(i) => i as usize,
-                    _ => return Err("copy expects an integer count".into()),
+                    _ => return Err(ps_error("typecheck", "copy")),
                 };
 
                 // Ensure the stack has enough values
                 let stack_len = self.opstack.len();
                 if n > stack_len 
                 {
-                    return Err("copy expects more operands on the stack".into());
+                    return Err(ps_error("stackunderflow", "copy"));
                 }
 
                 // Snapshot current stack
@@ -212,16 +292,16 @@ impl Interpreter
                 let size = match size_val 
                 {
                     Value::Int(i) => i,
-                    _ => return Err("dict expects an integer size".into()),
+                    _ => return Err(ps_error("typecheck", "dict")),
                 };
 
                 if size < 0 
                 {
-                    return Err("dict size cannot be negative".into());
+                    return Err(ps_error("rangecheck", "dict"));
                 }
 
                 // Create a new empty dictionary
-                let new_dict = Rc::new(RefCell::new(std::collections::HashMap::new()));
+                let new_dict = Rc::new(RefCell::new(PsDict::allocate(self.vm.global)));
 
                 // Wrap it as Value::Dict
                 self.push(Value::Dict(new_dict));
@@ -229,6 +309,51 @@ impl Interpreter
                 Ok(true)
             }
 
+            // Pushes a mark, << is the same operator under the name
+            // used to start a dictionary.
+            "mark" | "<<" =>
+            {
+                self.push(Value::Mark);
+
+                Ok(true)
+            }
+
+            // Collects the keys and values above the topmost mark into
+            // a new dictionary.
+            ">>" =>
+            {
+                let values = self.opstack_snapshot();
+                let mark = values.iter()
+                    .rposition(|value| matches!(value, Value::Mark))
+                    .ok_or_else(|| ps_error("unmatchedmark", ">>"))?;
+
+                let entries = &values[mark + 1..];
+                if !entries.len().is_multiple_of(2)
+                {
+                    return Err(ps_error("rangecheck", ">>"));
+                }
+
+                let mut new_dict = PsDict::allocate(self.vm.global);
+                for pair in entries.chunks(2)
+                {
+                    if new_dict.is_global() && pair[1].is_local_composite()
+                    {
+                        return Err(ps_error("invalidaccess", ">>"));
+                    }
+
+                    new_dict.insert(dict_key(pair[0].clone(), ">>")?, pair[1].clone());
+                }
+
+                // Only take the entries off once nothing can fail.
+                for _ in mark..values.len()
+                {
+                    self.pop()?;
+                }
+                self.push(Value::Dict(Rc::new(RefCell::new(new_dict))));
+
+                Ok(true)
+            }
+
             "begin" =>
             {
                 // Pop value from operand stack
@@ -238,31 +363,44 @@ impl Interpreter
                 let new_dict = match dict_val 
                 {
                     Value::Dict(d) => d,
-                    _ => return Err("begin expects a dictionary".into()),
+                    _ => return Err(ps_error("typecheck", "begin")),
                 };
 
-                // Push dictionary onto dictionary stack
-                let env_ref = self.dict.env(); // persistent dicitonary stack reference
+                if !new_dict.borrow().access().can_read()
+                {
+                    return Err(ps_error("invalidaccess", "begin"));
+                }
+
+                // Push dictionary onto dictionary stack (the running
+                // activation's frames inside a lexical procedure call)
+                let (env_ref, _) = self.definition_frames();
                 let mut env  = env_ref.borrow_mut(); // borrow 
                 env.push(new_dict);
 
+                let depth = env.len();
+                self.host.log.message(LogLevel::Debug, || format!("begin: dictionary stack depth {}", depth));
+
                 Ok(true)
             }
 
             "end" =>
             {
                 // Borrow the environment stack safely
-                let env_ref = self.dict.env();
+                let (env_ref, base) = self.definition_frames();
                 let mut env = env_ref.borrow_mut();
 
-                // You must not remove the bottom dictionary
-                if env.len() <= 1 
+                // You must not remove the bottom dictionaries, or the
+                // local dictionary of a lexical procedure call
+                if env.len() <= base
                 {
-                    return Err("end cannot pop bottom dictionary".into());
+                    return Err(ps_error("dictstackunderflow", "end"));
                 }
 
                 env.pop();
 
+                let depth = env.len();
+                self.host.log.message(LogLevel::Debug, || format!("end: dictionary stack depth {}", depth));
+
                 Ok(true)
             }
 
@@ -272,11 +410,13 @@ impl Interpreter
 
                 let len = match object 
                 {
+                    Value::Str(string) if !string.access().can_read() => return Err(ps_error("invalidaccess", "length")),
                     Value::Str(string) => string.len() as i32,
 
+                    Value::Dict(dictionary) if !dictionary.borrow().access().can_read() => return Err(ps_error("invalidaccess", "length")),
                     Value::Dict(dictionary) => dictionary.borrow().len() as i32,
 
-                    _ => return Err("length expects string, dict".into()),
+                    _ => return Err(ps_error("typecheck", "length")),
                 };
 
                 self.push(Value::Int(len));
@@ -293,101 +433,107 @@ impl Interpreter
                 // Then get the name associated with that value.
                 let name = self.pop()?;
 
-                if let Value::Name(n) = name
+                if let Value::Name(n) | Value::ExecName(n) = name
                 {
-                    match (&self.scope_mode, &value) 
+                    // The dictionary being written into must allow it, and a
+                    // global dictionary can not hold local objects.
+                    let target = self.current_dict();
+                    if !target.borrow().access().can_write()
+                        || (target.borrow().is_global() && value.is_local_composite())
                     {
-                        // LEXICAL: capture the environment at definition time
-                        ( ScopeMode::Lexical, Value::Procedure(body, _) ) =>
-                        {
-                             // Deep-copy the whole dictionary stack into a new EnvRef,
-                            // but make sure the borrow does NOT live past this inner block.
-                            let snapshot_vec: Vec<Dict> =
-                            {
-                                // Get current environment (vector of Dict = Rc<RefCell<HashMap<...>>>)
-                                let env_ref = self.dict.env();
-                                let env_borrow = env_ref.borrow();
-
-                                let mut snapshot = Vec::new();
-                                for dict_ref in env_borrow.iter()
-                                {
-                                    // &HashMap<String, Value>
-                                    let dict = dict_ref.borrow();
-
-                                    // deep copy the map
-                                    let cloned_map = dict.clone();
-
-                                    // wrap in a new Rc<RefCell<_>>
-                                    let new_dict: Dict = Rc::new(RefCell::new(cloned_map));
-                                    snapshot.push(new_dict);
-                                }
-
-                                snapshot   // <- returned out of the block
-                            }; // <- env_borrow and env_ref are dropped RIGHT HERE
-
-                            // Wrap the cloned vec in a new EnvRef
-                            let captured_env = Rc::new(RefCell::new(snapshot_vec));
-
-                            // Store procedure with its frozen lexical environment
-                            let captured = Value::Procedure(body.clone(), Some(captured_env));
-                            self.dict.define(&n, captured);
-                        }
-                        // DYNAMIC: store the procedure as is (no captured env)
-                        _ =>
-                        {
-                            println!("DEBUG DEF: defining '{}' as {:?}", n, value);
+                        return Err(ps_error("invalidaccess", "def"));
+                    }
 
-                            self.dict.define(&n, value);
-                        }
+                    // Builtins are looked up before any dictionary, so
+                    // this definition will never be found by name.
+                    if OPERATORS.iter().any(|(operator, _, _)| *operator == n)
+                    {
+                        self.host.log.message(LogLevel::Warn, || format!("def /{} is hidden by the builtin operator {}", n, n));
                     }
+                    self.host.log.message(LogLevel::Debug, || format!("def /{} {}", n, format_value(&value)));
+
+                    // A lexical procedure captured its environment when it
+                    // was created (pushed, scanned by token or marked by the
+                    // lexical operator) and a dynamic one has none, either
+                    // way it is stored unchanged. In lexical mode the only
+                    // procedures without an environment are the ones made
+                    // dynamic on purpose, by the dynamic operator or by
+                    // being written inside a dynamic procedure, so def
+                    // must not capture them.
+                    self.vm.record_dict(&target);
+                    let value = value.stored_in(&target);
+                    target.borrow_mut().insert(n, value);
 
                     Ok(true)
                 }
                 else
                 {
                     // Variable name was invalid.
-                    Err("def expects a literal name".to_string())
+                    Err(ps_error("typecheck", "def"))
                 }
             }
 
             "get" =>
             {
-                // Pop index
+                // Pop index (or key when reading a dictionary)
                 let index_val = self.pop()?;
 
+                // Pop string or dictionary
+                let container_val = self.pop()?;
+
+                // Dictionaries are read by key
+                if let Value::Dict(dictionary) = container_val
+                {
+                    let key = dict_key(index_val, "get")?;
+                    let dictionary = dictionary.borrow();
+
+                    if !dictionary.access().can_read()
+                    {
+                        return Err(ps_error("invalidaccess", "get"));
+                    }
+
+                    let value = dictionary.get(&key).cloned().ok_or_else(|| ps_error("undefined", "get"))?.retrieved();
+                    drop(dictionary);
+
+                    self.push(value);
+                    return Ok(true);
+                }
+
                 // Make sure its a valid integer value
                 let index = match index_val
                 {
                     Value::Int(i) => i,
-                    _ => return Err("get expects integer index".into()),
+                    _ => return Err(ps_error("typecheck", "get")),
                 };
 
                 // Invalid index should throw error
                 if index < 0
                 {
-                    return Err("get index out of bounds".into());
+                    return Err(ps_error("rangecheck", "get"));
                 }
 
-                // Pop string
-                let string_val = self.pop()?;
-
                 // Make sure string is valid
-                let string = match string_val
+                let string = match container_val
                 {
                     Value::Str(string_to_validate) => string_to_validate,
-                    _ => return Err("get expects string".into()),
+                    _ => return Err(ps_error("typecheck", "get")),
                 };
 
+                if !string.access().can_read()
+                {
+                    return Err(ps_error("invalidaccess", "get"));
+                }
+
                 let i = index as usize;
 
                 // Index greater than string length should throw error
                 if i >= string.len()
                 {
-                    return Err("get index out of bounds".into());
+                    return Err(ps_error("rangecheck", "get"));
                 }
 
-                // Convert char to i32 ASCII code
-                let target_character = string.as_bytes()[i] as i32;
+                // Byte value of the character
+                let target_character = string.get(i).unwrap() as i32;
 
                 // Push to stack
                 self.push(Value::Int(target_character));
@@ -403,13 +549,13 @@ impl Interpreter
                 let count = match count_val
                 {
                     Value::Int(i) => i,
-                    _ => return Err("getinterval expects integer count".into()),
+                    _ => return Err(ps_error("typecheck", "getinterval")),
                 };
 
                 // Throws error if count is negative
                 if count < 0
                 {
-                    return Err("getinterval count cannot be negative".into());
+                    return Err(ps_error("rangecheck", "getinterval"));
                 }
 
                 // Pop index
@@ -419,13 +565,13 @@ impl Interpreter
                 let index = match index_val
                 {
                     Value::Int(i) => i,
-                    _ => return Err("getinterval expects integer index".into()),
+                    _ => return Err(ps_error("typecheck", "getinterval")),
                 };
 
                 // Throws error if index is negative
                 if index < 0
                 {
-                    return Err("getinterval index cannot be negative".into());
+                    return Err(ps_error("rangecheck", "getinterval"));
                 }
 
                 // Pop string
@@ -435,20 +581,25 @@ impl Interpreter
                 let string = match string_val
                 {
                     Value::Str(string_to_check) => string_to_check,
-                    _ => return Err("getinterval expects string".into()),
+                    _ => return Err(ps_error("typecheck", "getinterval")),
                 };
 
+                if !string.access().can_read()
+                {
+                    return Err(ps_error("invalidaccess", "getinterval"));
+                }
+
                 let string_index = index as usize;
                 let character_count = count as usize;
 
                 // Bounds check
                 if string_index + character_count > string.len()
                 {
-                    return Err("getinterval range error".into());
+                    return Err(ps_error("rangecheck", "getinterval"));
                 }
 
-                // Extract substring
-                let substring = string[string_index..string_index + character_count].to_string();
+                // The substring shares storage with the original string
+                let substring = string.interval(string_index, character_count).unwrap();
 
                 // Push new string result
                 self.push(Value::Str(substring));
@@ -465,7 +616,7 @@ impl Interpreter
                 let source = match source_val
                 {
                     Value::Str(string_to_check) => string_to_check,
-                    _ => return Err("putinterval expects source string".into()),
+                    _ => return Err(ps_error("typecheck", "putinterval")),
                 };
 
                 // Pop index
@@ -475,13 +626,13 @@ impl Interpreter
                 let index = match index_val
                 {
                     Value::Int(i) => i,
-                    _ => return Err("putinterval expects integer index".into()),
+                    _ => return Err(ps_error("typecheck", "putinterval")),
                 };
 
                 // Negative index should throw error
                 if index < 0
                 {
-                    return Err("putinterval index cannot be negative".into());
+                    return Err(ps_error("rangecheck", "putinterval"));
                 }
 
                 let string_index = index as usize;
@@ -490,26 +641,440 @@ impl Interpreter
                 let target_val = self.pop()?;
 
                 // Ensure string is valid
-                let mut target = match target_val
+                let target = match target_val
                 {
                     Value::Str(string_to_check) => string_to_check,
-                    _ => return Err("putinterval expects target string".into()),
+                    _ => return Err(ps_error("typecheck", "putinterval")),
                 };
 
+                // The target is written and the source is read
+                if !target.access().can_write() || !source.access().can_read()
+                {
+                    return Err(ps_error("invalidaccess", "putinterval"));
+                }
+
                 // Bounds check
                 if string_index + source.len() > target.len()
                 {
-                    return Err("putinterval range error".into());
+                    return Err(ps_error("rangecheck", "putinterval"));
+                }
+
+                // Mutate the target string in place, every reference
+                // to it sees the new contents.
+                self.vm.record_string(&target);
+                target.put_interval(string_index, &source.to_bytes())?;
+
+                Ok(true)
+            }
+
+            // Allocates a new string of n zero bytes.
+            "string" =>
+            {
+                // Pop the size argument
+                let size_val = self.pop()?;
+
+                // Make sure size is a valid integer
+                let size = match size_val
+                {
+                    Value::Int(i) => i,
+                    _ => return Err(ps_error("typecheck", "string")),
+                };
+
+                if size < 0
+                {
+                    return Err(ps_error("rangecheck", "string"));
+                }
+
+                // PostScript strings are at most 65535 bytes long
+                if size > 65535
+                {
+                    return Err(ps_error("limitcheck", "string"));
+                }
+
+                self.push(Value::Str(PsString::allocate(vec![0; size as usize], self.vm.global)));
+
+                Ok(true)
+            }
+
+            // Stores a byte into a string at the given index, or a value
+            // into a dictionary under the given key.
+            "put" =>
+            {
+                // Pop the value to store
+                let stored_val = self.pop()?;
+
+                // Pop index (or key when writing a dictionary)
+                let index_val = self.pop()?;
+
+                // Pop string or dictionary
+                let container_val = self.pop()?;
+
+                // Dictionaries are written by key
+                if let Value::Dict(dictionary) = container_val
+                {
+                    let key = dict_key(index_val, "put")?;
+
+                    if !dictionary.borrow().access().can_write()
+                        || (dictionary.borrow().is_global() && stored_val.is_local_composite())
+                    {
+                        return Err(ps_error("invalidaccess", "put"));
+                    }
+
+                    self.vm.record_dict(&dictionary);
+                    let stored_val = stored_val.stored_in(&dictionary);
+                    dictionary.borrow_mut().insert(key, stored_val);
+                    return Ok(true);
+                }
+
+                // Make sure the value fits in a byte
+                let byte = match stored_val
+                {
+                    Value::Int(i) if (0..=255).contains(&i) => i as u8,
+                    Value::Int(_) => return Err(ps_error("rangecheck", "put")),
+                    _ => return Err(ps_error("typecheck", "put")),
+                };
+
+                // Make sure index is a valid integer
+                let index = match index_val
+                {
+                    Value::Int(i) => i,
+                    _ => return Err(ps_error("typecheck", "put")),
+                };
+
+                if index < 0
+                {
+                    return Err(ps_error("rangecheck", "put"));
+                }
+
+                // Make sure string is valid
+                let string = match container_val
+                {
+                    Value::Str(string_to_check) => string_to_check,
+                    _ => return Err(ps_error("typecheck", "put")),
+                };
+
+                if !string.access().can_write()
+                {
+                    return Err(ps_error("invalidaccess", "put"));
+                }
+
+                // Every reference to the string sees the new byte
+                self.vm.record_string(&string);
+                string.put(index as usize, byte).map_err(|_| ps_error("rangecheck", "put"))?;
+
+                Ok(true)
+            }
+
+            // Looks for seek inside string. On success pushes the part after
+            // the match, the match and the part before it, then true.
+            // Otherwise pushes the original string and false.
+            "search" =>
+            {
+                let (string, seek) = self.pop_search_operands("search")?;
+                let haystack = string.to_bytes();
+                let needle = seek.to_bytes();
+
+                match find_bytes(&haystack, &needle)
+                {
+                    Some(position) =>
+                    {
+                        // All three results share storage with the original string
+                        let after = position + needle.len();
+                        self.push(Value::Str(string.interval(after, string.len() - after).unwrap()));
+                        self.push(Value::Str(string.interval(position, needle.len()).unwrap()));
+                        self.push(Value::Str(string.interval(0, position).unwrap()));
+                        self.push(Value::Bool(true));
+                    }
+                    None =>
+                    {
+                        self.push(Value::Str(string));
+                        self.push(Value::Bool(false));
+                    }
+                }
+
+                Ok(true)
+            }
+
+            // Like search, but seek only matches at the start of string.
+            // On success pushes the remainder and the match, then true.
+            "anchorsearch" =>
+            {
+                let (string, seek) = self.pop_search_operands("anchorsearch")?;
+                let haystack = string.to_bytes();
+                let needle = seek.to_bytes();
+
+                if haystack.starts_with(&needle)
+                {
+                    self.push(Value::Str(string.interval(needle.len(), string.len() - needle.len()).unwrap()));
+                    self.push(Value::Str(string.interval(0, needle.len()).unwrap()));
+                    self.push(Value::Bool(true));
+                }
+                else
+                {
+                    self.push(Value::Str(string));
+                    self.push(Value::Bool(false));
+                }
+
+                Ok(true)
+            }
+
+            // Scans one PostScript object from the front of a string.
+            // On success pushes the rest of the string and the object, then
+            // true. If the string only holds whitespace pushes false.
+            "token" =>
+            {
+                let string_val = self.pop()?;
+
+                // Make sure string is valid
+                let string = match string_val
+                {
+                    Value::Str(string_to_check) => string_to_check,
+                    _ => return Err(ps_error("typecheck", "token")),
+                };
+
+                if !string.access().can_read()
+                {
+                    return Err(ps_error("invalidaccess", "token"));
                 }
 
-                // Mutate the target string
-                for (i, current_character) in source.chars().enumerate()
+                match scan_token(&string.to_bytes(), self.vm.global).map_err(|e| ps_error("syntaxerror", "token").with_detail(e))?
                 {
-                    target.replace_range(string_index + i .. string_index + i + 1, &current_character.to_string());
+                    Some((token, consumed)) =>
+                    {
+                        // A scanned procedure is created here, so in lexical
+                        // mode it captures this environment the way a
+                        // procedure literal does when it is pushed.
+                        let object = match token
+                        {
+                            Token::Literal(Value::Procedure(procedure), _) if self.current_scope() == ScopeMode::Lexical =>
+                                Value::Procedure(self.capture(&procedure, None)),
+                            Token::Literal(v, _) => v,
+                            Token::ExecName(name, _) => Value::ExecName(name),
+                        };
+
+                        // The scanner never reads past the end of the string.
+                        let rest = string
+                            .interval(consumed, string.len() - consumed)
+                            .expect("token consumed more than the string");
+
+                        self.push(Value::Str(rest));
+                        self.push(object);
+                        self.push(Value::Bool(true));
+                    }
+                    None => self.push(Value::Bool(false)),
                 }
 
-                // Push mutated string back
-                self.push(Value::Str(target));
+                Ok(true)
+            }
+
+            // Reduces the access of a composite object to read only.
+            "readonly" =>
+            {
+                let value = self.pop()?;
+                let restricted = restrict_access(&mut self.vm, value, Access::ReadOnly, "readonly")?;
+                self.push(restricted);
+                Ok(true)
+            }
+
+            // Reduces the access of a string or procedure to execute only.
+            "executeonly" =>
+            {
+                let value = self.pop()?;
+
+                if let Value::Dict(_) = value
+                {
+                    return Err(ps_error("typecheck", "executeonly"));
+                }
+
+                let restricted = restrict_access(&mut self.vm, value, Access::ExecuteOnly, "executeonly")?;
+                self.push(restricted);
+                Ok(true)
+            }
+
+            // Removes all access to a composite object.
+            "noaccess" =>
+            {
+                let value = self.pop()?;
+                let restricted = restrict_access(&mut self.vm, value, Access::NoAccess, "noaccess")?;
+                self.push(restricted);
+                Ok(true)
+            }
+
+            // Pushes true if the object's contents may be read.
+            "rcheck" =>
+            {
+                let value = self.pop()?;
+                let access = value.access().ok_or_else(|| ps_error("typecheck", "rcheck"))?;
+                self.push(Value::Bool(access.can_read()));
+                Ok(true)
+            }
+
+            // Pushes true if the object's contents may be written.
+            "wcheck" =>
+            {
+                let value = self.pop()?;
+                let access = value.access().ok_or_else(|| ps_error("typecheck", "wcheck"))?;
+                self.push(Value::Bool(access.can_write()));
+                Ok(true)
+            }
+
+            // Pushes the type of any object as a name, such as /integertype.
+            "type" =>
+            {
+                let value = self.pop()?;
+
+                self.push(Value::Name(value.type_name().to_string()));
+
+                Ok(true)
+            }
+
+            // Converts a number or numeric string to an integer,
+            // truncating reals toward zero.
+            "cvi" =>
+            {
+                let value = self.pop()?;
+
+                let number = match value
+                {
+                    Value::Str(s) => parse_number(&s, "cvi")?,
+                    other => other,
+                };
+
+                let result = match number
+                {
+                    Value::Int(i) => i,
+                    Value::Real(f) =>
+                    {
+                        let truncated = f.trunc();
+                        if !(i32::MIN as f64..=i32::MAX as f64).contains(&truncated)
+                        {
+                            return Err(ps_error("rangecheck", "cvi"));
+                        }
+                        truncated as i32
+                    }
+                    _ => return Err(ps_error("typecheck", "cvi")),
+                };
+
+                self.push(Value::Int(result));
+
+                Ok(true)
+            }
+
+            // Converts a number or numeric string to a real.
+            "cvr" =>
+            {
+                let value = self.pop()?;
+
+                let number = match value
+                {
+                    Value::Str(s) => parse_number(&s, "cvr")?,
+                    other => other,
+                };
+
+                let result = match number
+                {
+                    Value::Int(i) => i as f64,
+                    Value::Real(f) => f,
+                    _ => return Err(ps_error("typecheck", "cvr")),
+                };
+
+                self.push(Value::Real(result));
+
+                Ok(true)
+            }
+
+            // Converts a string to a name with the same text.
+            "cvn" =>
+            {
+                let value = self.pop()?;
+
+                let name = match value
+                {
+                    Value::Str(s) if !s.access().can_read() => return Err(ps_error("invalidaccess", "cvn")),
+                    Value::Str(s) => s.to_string_lossy(),
+                    Value::Name(n) => n,
+                    _ => return Err(ps_error("typecheck", "cvn")),
+                };
+
+                self.push(Value::Name(name));
+
+                Ok(true)
+            }
+
+            // Writes the text form of any object into a string and pushes
+            // the part of the string that was written.
+            "cvs" =>
+            {
+                let string = match self.pop()?
+                {
+                    Value::Str(s) => s,
+                    _ => return Err(ps_error("typecheck", "cvs")),
+                };
+
+                let value = self.pop()?;
+
+                let text = match value
+                {
+                    Value::Int(i) => i.to_string().into_bytes(),
+                    Value::Real(f) => format_real(f).into_bytes(),
+                    Value::Bool(b) => b.to_string().into_bytes(),
+                    Value::Str(s) if !s.access().can_read() => return Err(ps_error("invalidaccess", "cvs")),
+                    Value::Str(s) => s.to_bytes(),
+                    Value::Name(n) | Value::ExecName(n) => n.into_bytes(),
+                    _ => b"--nostringval--".to_vec(),
+                };
+
+                let written = write_prefix(&mut self.vm, &string, &text, "cvs")?;
+                self.push(Value::Str(written));
+
+                Ok(true)
+            }
+
+            // Writes a number in the given radix into a string and pushes
+            // the part of the string that was written.
+            "cvrs" =>
+            {
+                let string = match self.pop()?
+                {
+                    Value::Str(s) => s,
+                    _ => return Err(ps_error("typecheck", "cvrs")),
+                };
+
+                let radix = match self.pop()?
+                {
+                    Value::Int(r) => r,
+                    _ => return Err(ps_error("typecheck", "cvrs")),
+                };
+
+                if !(2..=36).contains(&radix)
+                {
+                    return Err(ps_error("rangecheck", "cvrs"));
+                }
+
+                let number = self.pop()?;
+
+                let text = match (number, radix)
+                {
+                    // Base 10 keeps reals as reals, like cvs.
+                    (Value::Int(i), 10) => i.to_string(),
+                    (Value::Real(f), 10) => format_real(f),
+
+                    // Other bases write the 32 bit two's complement pattern.
+                    (Value::Int(i), _) => to_radix(i as u32, radix as u32),
+                    (Value::Real(f), _) =>
+                    {
+                        let truncated = f.trunc();
+                        if !(i32::MIN as f64..=i32::MAX as f64).contains(&truncated)
+                        {
+                            return Err(ps_error("rangecheck", "cvrs"));
+                        }
+                        to_radix(truncated as i32 as u32, radix as u32)
+                    }
+                    _ => return Err(ps_error("typecheck", "cvrs")),
+                };
+
+                let written = write_prefix(&mut self.vm, &string, text.as_bytes(), "cvrs")?;
+                self.push(Value::Str(written));
 
                 Ok(true)
             }
@@ -519,14 +1084,7 @@ impl Interpreter
                 let b = self.pop()?;
                 let a = self.pop()?;
 
-                let result = match (a, b)
-                {
-                    (Value::Int(x), Value::Int(y)) => x == y,
-                    (Value::Real(x), Value::Real(y)) => x == y,
-                    (Value::Bool(x), Value::Bool(y)) => x == y,
-                    (Value::Str(x), Value::Str(y)) => x == y,
-                    _ => return Err("eq type error".into()),
-                };
+                let result = values_equal(&a, &b, "eq")?;
 
                 self.push(Value::Bool(result));
                 Ok(true)
@@ -537,14 +1095,7 @@ impl Interpreter
                 let b = self.pop()?;
                 let a = self.pop()?;
 
-                let result = match (a, b)
-                {
-                    (Value::Int(x), Value::Int(y)) => x != y,
-                    (Value::Real(x), Value::Real(y)) => x != y,
-                    (Value::Bool(x), Value::Bool(y)) => x != y,
-                    (Value::Str(x), Value::Str(y)) => x != y,
-                    _ => return Err("ne type error".into()),
-                };
+                let result = !values_equal(&a, &b, "ne")?;
 
                 self.push(Value::Bool(result));
                 Ok(true)
@@ -555,12 +1106,18 @@ impl Interpreter
                 let b = self.pop()?;
                 let a = self.pop()?;
 
-                let result = match (a, b)
-                {
-                    (Value::Int(x), Value::Int(y)) => x > y,
-                    (Value::Real(x), Value::Real(y)) => x > y,
-                    _ => return Err("gt expects integers".into()),
-                };
+                let result = compare_values(&a, &b, "gt")? == Some(Ordering::Greater);
+
+                self.push(Value::Bool(result));
+                Ok(true)
+            }
+
+            "ge" =>
+            {
+                let b = self.pop()?;
+                let a = self.pop()?;
+
+                let result = matches!(compare_values(&a, &b, "ge")?, Some(Ordering::Greater | Ordering::Equal));
 
                 self.push(Value::Bool(result));
                 Ok(true)
@@ -571,12 +1128,18 @@ impl Interpreter
                 let b = self.pop()?;
                 let a = self.pop()?;
 
-                let result = match (a, b)
-                {
-                    (Value::Int(x), Value::Int(y)) => x < y,
-                    (Value::Real(x), Value::Real(y)) => x < y,
-                    _ => return Err("lt expects integers".into()),
-                };
+                let result = compare_values(&a, &b, "lt")? == Some(Ordering::Less);
+
+                self.push(Value::Bool(result));
+                Ok(true)
+            }
+
+            "le" =>
+            {
+                let b = self.pop()?;
+                let a = self.pop()?;
+
+                let result = matches!(compare_values(&a, &b, "le")?, Some(Ordering::Less | Ordering::Equal));
 
                 self.push(Value::Bool(result));
                 Ok(true)
@@ -590,7 +1153,7 @@ impl Interpreter
                 let result = match (a, b)
                 {
                     (Value::Bool(x), Value::Bool(y)) => x && y,
-                    _ => return Err("and expects booleans".into()),
+                    _ => return Err(ps_error("typecheck", "and")),
                 };
 
                 self.push(Value::Bool(result));
@@ -605,7 +1168,7 @@ impl Interpreter
                 let result = match (a, b)
                 {
                     (Value::Bool(x), Value::Bool(y)) => x || y,
-                    _ => return Err("or expects booleans".into()),
+                    _ => return Err(ps_error("typecheck", "or")),
                 };
 
                 self.push(Value::Bool(result));
@@ -619,7 +1182,7 @@ impl Interpreter
                 let result = match a
                 {
                     Value::Bool(x) => !x,
-                    _ => return Err("not expects boolean".into()),
+                    _ => return Err(ps_error("typecheck", "not")),
                 };
 
                 self.push(Value::Bool(result));
@@ -632,11 +1195,13 @@ impl Interpreter
 
                 match string_to_print
                 {
+                    Value::Str(string) if !string.access().can_read() => return Err(ps_error("invalidaccess", "print")),
                     Value::Str(string) =>
                     {
-                        print!("{}", string); // no newline
+                        // Write the raw bytes so binary data is not altered.
+                        self.write_output(&string.to_bytes(), "print")?; // no newline
                     }
-                    _ => return Err("print expects a string".into()),
+                    _ => return Err(ps_error("typecheck", "print")),
                 }
 
                 Ok(true)
@@ -645,34 +1210,61 @@ impl Interpreter
             "=" =>
             {
                 let value = self.pop()?;
+                self.write_line(&format_text(&value), "=")?;
+
+                Ok(true)
+            }
+
+            "==" =>
+            {
+                let value = self.pop()?;
+                self.write_line(&format_value(&value), "==")?;
+
+                Ok(true)
+            }
 
-                match value
+            // Writes every value on the stack, top first, the way =
+            // does, and leaves the stack alone.
+            "stack" =>
+            {
+                for value in self.opstack_snapshot().iter().rev()
                 {
-                    Value::Int(i) => println!("{}", i),
-                    Value::Real(f) => println!("{}", f),
-                    Value::Bool(b) => println!("{}", b),
-                    Value::Str(s) => println!("{}", s),
-                    Value::Name(n) => println!("/{}", n),
-                    Value::Dict(_) => println!("--dict--"),
-                    Value::Procedure(_, _) => println!("--procedure--"),
+                    self.write_line(&format_text(value), "stack")?;
                 }
 
                 Ok(true)
             }
 
-            "==" =>
+            // Same as stack but written the way == does.
+            "pstack" =>
             {
-                let value = self.pop()?;
+                for value in self.opstack_snapshot().iter().rev()
+                {
+                    self.write_line(&format_value(value), "pstack")?;
+                }
+
+                Ok(true)
+            }
 
-                match value
+            // Runs the object on top of the stack: procedures are called
+            // the way if calls them and executable names run as if they
+            // stood in the program where exec does. Anything else is
+            // pushed back unchanged.
+            "exec" =>
+            {
+                match self.pop()?
                 {
-                    Value::Int(i) => println!("{}", i),
-                    Value::Real(f) => println!("{}", f),
-                    Value::Bool(b) => println!("{}", b),
-                    Value::Str(s) => println!("({})", s), // literal string
-                    Value::Name(n) => println!("/{}", n),
-                    Value::Dict(_) => println!("--dict--"),
-                    Value::Procedure(_, _) => println!("--procedure--"),
+                    Value::Procedure(procedure) => self.call_procedure(&procedure)?,
+                    Value::ExecName(name) =>
+                    {
+                        let position = self.call_stack.last()
+                            .map(|call| call.position.clone())
+                            .unwrap_or_else(|| Position { source: Rc::from("exec"), line: 1, column: 1 });
+                        let env = self.activation.as_ref().map(|activation| Rc::clone(&activation.frames));
+
+                        self.exec_tokens(&[Token::ExecName(name, position)], env)?;
+                    }
+                    other => self.push(other),
                 }
 
                 Ok(true)
@@ -686,21 +1278,21 @@ impl Interpreter
                 // Make sure its a valid procedure
                 let procedure = match procedure_value
                 {
-                    Value::Procedure(body, captured) => (body, captured),
-                    _ => return Err("if expects a procedure".into()),
+                    Value::Procedure(procedure) => procedure,
+                    _ => return Err(ps_error("typecheck", "if")),
                 };
 
                 // Make sure its a valid boolean
                 let boolean = match boolean_value
                 {
                     Value::Bool(b) => b,
-                    _ => return Err("if expects boolean".into()),
+                    _ => return Err(ps_error("typecheck", "if")),
                 };
 
                 // Only execute if boolean is true
                 if boolean
                 {
-                    self.exec_tokens(&procedure.0, procedure.1)?;
+                    self.call_procedure(&procedure)?;
                 }
 
                 Ok(true)
@@ -715,32 +1307,32 @@ impl Interpreter
                 // Makes sure false procedure is valid
                 let false_proc = match false_procedure_value
                 {
-                    Value::Procedure(body, captured) => (body, captured),
-                    _ => return Err("ifelse expects a procedure".into()),
+                    Value::Procedure(procedure) => procedure,
+                    _ => return Err(ps_error("typecheck", "ifelse")),
                 };
 
                 // makes sure true procedure is valid
                 let true_proc = match true_procedure_value
                 {
-                    Value::Procedure(body, captured) => (body, captured),
-                    _ => return Err("ifelse expects a procedure".into()),
+                    Value::Procedure(procedure) => procedure,
+                    _ => return Err(ps_error("typecheck", "ifelse")),
                 };
 
                 // Makes sure boolean is valid
                 let boolean = match boolean_value
                 {
                     Value::Bool(b) => b,
-                    _ => return Err("ifelse expects boolean".into()),
+                    _ => return Err(ps_error("typecheck", "ifelse")),
                 };
 
                 // Execute true procedure if true, otherwise execute false procedure
                 if boolean
                 {
-                    self.exec_tokens(&true_proc.0, true_proc.1)?;
+                    self.call_procedure(&true_proc)?;
                 }
                 else
                 {
-                    self.exec_tokens(&false_proc.0, false_proc.1)?;
+                    self.call_procedure(&false_proc)?;
                 }
 
                 Ok(true)
@@ -754,21 +1346,22 @@ impl Interpreter
                 // Make sure procedure is valid
                 let procedure = match procedure_value
                 {
-                    Value::Procedure(body, captured) => (body, captured),
-                    _ => return Err("repeat expects a procedure".into()),
+                    Value::Procedure(procedure) => procedure,
+                    _ => return Err(ps_error("typecheck", "repeat")),
                 };
 
                 // Make sure count is valid integer
                 let count = match count_value
                 {
                     Value::Int(n) if n >= 0 => n,
-                    _ => return Err("repeat expects nonnegative integer".into()),
+                    Value::Int(_) => return Err(ps_error("rangecheck", "repeat")),
+                    _ => return Err(ps_error("typecheck", "repeat")),
                 };
 
                 // Execute procedure until count is reached
                 for _ in 0..count
                 {
-                    self.exec_tokens(&procedure.0, procedure.1.clone())?;
+                    self.call_procedure(&procedure)?;
                 }
 
                 Ok(true)
@@ -784,55 +1377,165 @@ impl Interpreter
                 // Make sure procedure is valid
                 let procedure = match procedure_value
                 {
-                    Value::Procedure(body, captured) => (body, captured),
-                    _ => return Err("for expects procedure".into()),
+                    Value::Procedure(procedure) => procedure,
+                    _ => return Err(ps_error("typecheck", "for")),
                 };
 
                 // Makes sure initial value is valid integer
                 let initial = match initial_value
                 {
                     Value::Int(i) => i,
-                    _ => return Err("for expects integer initial".into()),
+                    _ => return Err(ps_error("typecheck", "for")),
                 };
 
-                // Makes sure increment value is valid
+                // Makes sure increment value is valid integer. A zero
+                // increment counts as going up, so the loop runs until
+                // the procedure fails if initial is not above limit.
                 let increment = match increment_value
                 {
-                    Value::Int(i) if i != 0 => i,
-                    _ => return Err("for expects nonzero increment integer".into()),
+                    Value::Int(i) => i,
+                    _ => return Err(ps_error("typecheck", "for")),
                 };
 
                 // Makes sure limit value is valid integer
                 let limit = match limit_value
                 {
                     Value::Int(i) => i,
-                    _ => return Err("for expects integer limit".into()),
+                    _ => return Err(ps_error("typecheck", "for")),
                 };
 
-                let mut i = initial;
-                
-                if increment > 0
+                // The loop also stops when the control variable would
+                // go past the largest or smallest integer.
+                let mut i = Some(initial);
+
+                while let Some(current) = i.filter(|&i| if increment >= 0 { i <= limit } else { i >= limit })
                 {
-                    while i <= limit
-                    {
-                        self.push(Value::Int(i));
-                        self.exec_tokens(&procedure.0, procedure.1.clone())?;
-                        i += increment;
-                    }
+                    self.push(Value::Int(current));
+                    self.call_procedure(&procedure)?;
+                    i = current.checked_add(increment);
                 }
-                else
+
+                Ok(true)
+            }
+
+            // Pushes true if new objects are being allocated in global VM.
+            "currentglobal" =>
+            {
+                self.push(Value::Bool(self.vm.global));
+                Ok(true)
+            }
+
+            // Chooses global (true) or local (false) VM for new objects.
+            "setglobal" =>
+            {
+                match self.pop()?
                 {
-                    while i >= limit
-                    {
-                        self.push(Value::Int(i));
-                        self.exec_tokens(&procedure.0, procedure.1.clone())?;
-                        i += increment;
-                    }
+                    Value::Bool(global) => self.vm.global = global,
+                    _ => return Err(ps_error("typecheck", "setglobal")),
                 }
 
                 Ok(true)
             }
 
+            // Pushes globaldict, the dictionary kept in global VM.
+            "globaldict" =>
+            {
+                let globaldict = self.dict.globaldict();
+                self.push(Value::Dict(globaldict));
+                Ok(true)
+            }
+
+            // Starts a new save and pushes the save object for it.
+            "save" =>
+            {
+                let serial = self.vm.save(&self.dict);
+                self.push(Value::Save(serial));
+                Ok(true)
+            }
+
+            // Rolls the dictionaries, strings and dictionary stack back to
+            // the state recorded by a save object.
+            "restore" =>
+            {
+                let serial = match self.pop()?
+                {
+                    Value::Save(serial) => serial,
+                    _ => return Err(ps_error("typecheck", "restore")),
+                };
+
+                self.vm.restore(serial, &self.dict, &self.opstack)?;
+                Ok(true)
+            }
+
+            // Pushes the name of the scoping mode used for top level code.
+            "currentscoping" =>
+            {
+                self.push(Value::Name(self.scope_mode.name().to_string()));
+                Ok(true)
+            }
+
+            // Chooses the scoping mode (/lexical or /dynamic) for top level
+            // code from now on. Existing procedures keep their own mode.
+            "setscoping" =>
+            {
+                let mode = match self.pop()?
+                {
+                    Value::Name(name) => ScopeMode::from_name(&name)
+                        .ok_or_else(|| ps_error("rangecheck", "setscoping"))?,
+                    _ => return Err(ps_error("typecheck", "setscoping")),
+                };
+
+                self.scope_mode = mode;
+                Ok(true)
+            }
+
+            // Marks a procedure as lexically scoped: it captures the
+            // environment it is in now and runs with it from then on.
+            "lexical" =>
+            {
+                let procedure = match self.pop()?
+                {
+                    Value::Procedure(procedure) => procedure,
+                    _ => return Err(ps_error("typecheck", "lexical")),
+                };
+
+                let captured = self.capture(&procedure, None);
+                self.push(Value::Procedure(captured));
+                Ok(true)
+            }
+
+            // Marks a procedure as dynamically scoped: it drops any captured
+            // environment and looks names up on the live dictionary stack.
+            "dynamic" =>
+            {
+                let procedure = match self.pop()?
+                {
+                    Value::Procedure(procedure) => procedure,
+                    _ => return Err(ps_error("typecheck", "dynamic")),
+                };
+
+                self.push(Value::Procedure(Procedure
+                {
+                    env: None,
+                    home: None,
+                    stored: None,
+                    ..procedure
+                }));
+                Ok(true)
+            }
+
+            // Turns the execution trace on or off, see exec_token_traced.
+            "settrace" =>
+            {
+                self.host.exec_trace = match self.pop()?
+                {
+                    Value::Bool(on) => on,
+                    _ => return Err(ps_error("typecheck", "settrace")),
+                };
+
+                Ok(true)
+            }
+
             // Clears all the values in the stack
             "clear" =>
             {
@@ -845,3 +1548,203 @@ impl Interpreter
         }
     }
 }
+
+impl Interpreter
+{
+    // Pops the string and seek operands shared by search and anchorsearch.
+    fn pop_search_operands(&mut self, operator: &str) -> Result<(PsString, PsString), PsError>
+    {
+        let seek = match self.pop()?
+        {
+            Value::Str(s) => s,
+            _ => return Err(ps_error("typecheck", operator)),
+        };
+
+        let string = match self.pop()?
+        {
+            Value::Str(s) => s,
+            _ => return Err(ps_error("typecheck", operator)),
+        };
+
+        if !string.access().can_read() || !seek.access().can_read()
+        {
+            return Err(ps_error("invalidaccess", operator));
+        }
+
+        Ok((string, seek))
+    }
+}
+
+// Finds the first position where needle appears in haystack.
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize>
+{
+    if needle.is_empty()
+    {
+        return Some(0);
+    }
+
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+// Reads a number out of a string for cvi and cvr.
+fn parse_number(string: &PsString, operator: &str) -> Result<Value, PsError>
+{
+    if !string.access().can_read()
+    {
+        return Err(ps_error("invalidaccess", operator));
+    }
+
+    let bytes = string.to_bytes();
+
+    match scan_token(&bytes, false).map_err(|e| ps_error("syntaxerror", operator).with_detail(e))?
+    {
+        Some((Token::Literal(number @ (Value::Int(_) | Value::Real(_)), _), consumed)) =>
+        {
+            // Anything after the number other than whitespace is an error.
+            if bytes[consumed..].trim_ascii().is_empty()
+            {
+                Ok(number)
+            }
+            else
+            {
+                Err(ps_error("syntaxerror", operator))
+            }
+        }
+        Some(_) => Err(ps_error("typecheck", operator)),
+        None => Err(ps_error("syntaxerror", operator)),
+    }
+}
+
+// Copies text into the start of string and returns the written part,
+// which shares storage with string. Fails if string is too short.
+fn write_prefix(vm: &mut VirtualMemory, string: &PsString, text: &[u8], operator: &str) -> Result<PsString, PsError>
+{
+    if !string.access().can_write()
+    {
+        return Err(ps_error("invalidaccess", operator));
+    }
+
+    if text.len() > string.len()
+    {
+        return Err(ps_error("rangecheck", operator));
+    }
+
+    vm.record_string(string);
+    string.put_interval(0, text)?;
+    Ok(string.interval(0, text.len()).unwrap())
+}
+
+// Writes an unsigned number in the given radix using upper case digits.
+fn to_radix(mut number: u32, radix: u32) -> String
+{
+    if number == 0
+    {
+        return "0".to_string();
+    }
+
+    let mut digits = Vec::new();
+    while number > 0
+    {
+        digits.push(std::char::from_digit(number % radix, radix).unwrap().to_ascii_uppercase());
+        number /= radix;
+    }
+
+    digits.iter().rev().collect()
+}
+
+// Equality used by eq and ne. Integers and reals compare by value,
+// strings and names compare by their text (so (abc) and /abc are equal),
+// and dictionaries and procedures are equal only when they are the same
+// object. Values of unrelated types are simply not equal.
+fn values_equal(a: &Value, b: &Value, operator: &str) -> Result<bool, PsError>
+{
+    let unreadable = |value: &Value| matches!(value, Value::Str(s) if !s.access().can_read());
+    if unreadable(a) || unreadable(b)
+    {
+        return Err(ps_error("invalidaccess", operator));
+    }
+
+    let result = match (a, b)
+    {
+        (Value::Int(x), Value::Int(y)) => x == y,
+        (Value::Int(x), Value::Real(y)) => (*x as f64) == *y,
+        (Value::Real(x), Value::Int(y)) => *x == (*y as f64),
+        (Value::Real(x), Value::Real(y)) => x == y,
+        (Value::Bool(x), Value::Bool(y)) => x == y,
+        (Value::Str(x), Value::Str(y)) => x == y,
+        (Value::Name(x) | Value::ExecName(x), Value::Name(y) | Value::ExecName(y)) => x == y,
+        (Value::Str(x), Value::Name(y) | Value::ExecName(y)) | (Value::Name(y) | Value::ExecName(y), Value::Str(x)) => x.to_bytes() == y.as_bytes(),
+        (Value::Dict(x), Value::Dict(y)) => Rc::ptr_eq(x, y),
+        (Value::Procedure(x), Value::Procedure(y)) => Rc::ptr_eq(&x.body, &y.body),
+        (Value::Save(x), Value::Save(y)) => x == y,
+        (Value::Mark, Value::Mark) => true,
+        _ => false,
+    };
+
+    Ok(result)
+}
+
+// Ordering used by gt, ge, lt and le. Numbers compare by value in any
+// int and real mix, strings compare byte by byte. Returns None when the
+// numbers are unordered (a NaN is involved). Strings without read
+// access can not be compared.
+fn compare_values(a: &Value, b: &Value, operator: &str) -> Result<Option<Ordering>, PsError>
+{
+    let unreadable = |value: &Value| matches!(value, Value::Str(s) if !s.access().can_read());
+    if unreadable(a) || unreadable(b)
+    {
+        return Err(ps_error("invalidaccess", operator));
+    }
+
+    let result = match (a, b)
+    {
+        (Value::Int(x), Value::Int(y)) => Some(x.cmp(y)),
+        (Value::Int(x), Value::Real(y)) => (*x as f64).partial_cmp(y),
+        (Value::Real(x), Value::Int(y)) => x.partial_cmp(&(*y as f64)),
+        (Value::Real(x), Value::Real(y)) => x.partial_cmp(y),
+        (Value::Str(x), Value::Str(y)) => Some(x.to_bytes().cmp(&y.to_bytes())),
+        _ => return Err(ps_error("typecheck", operator)),
+    };
+
+    Ok(result)
+}
+
+// Lowers the access attribute of a composite object. Access can only be
+// reduced, trying to raise it is an invalidaccess error. Strings and
+// procedures get a new object with the lower access, dictionaries are
+// changed in place so every reference sees it.
+fn restrict_access(vm: &mut VirtualMemory, value: Value, access: Access, operator: &str) -> Result<Value, PsError>
+{
+    let current = value.access().ok_or_else(|| ps_error("typecheck", operator))?;
+
+    if access > current
+    {
+        return Err(ps_error("invalidaccess", operator));
+    }
+
+    let restricted = match value
+    {
+        Value::Str(s) => Value::Str(s.with_access(access)),
+        Value::Procedure(procedure) => Value::Procedure(Procedure { access, ..procedure }),
+        Value::Dict(dictionary) =>
+        {
+            vm.record_dict(&dictionary);
+            dictionary.borrow_mut().set_access(access);
+            Value::Dict(dictionary)
+        }
+        other => other,
+    };
+
+    Ok(restricted)
+}
+
+// Dictionary keys are names; strings are accepted and converted.
+fn dict_key(key: Value, operator: &str) -> Result<String, PsError>
+{
+    match key
+    {
+        Value::Name(n) | Value::ExecName(n) => Ok(n),
+        Value::Str(s) => Ok(s.to_string_lossy()),
+        _ => Err(ps_error("typecheck", operator)),
+    }
+}
@@ -0,0 +1,345 @@
+// -----------------------------------------------------------------------------
+// File: debug.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// A step debugger. Before each token runs, exec_tokens asks the debugger
+// whether to stop there: at a breakpoint, or after a step command. While
+// stopped, commands read from a console inspect the stacks, change the
+// breakpoints and decide how far to run next.
+// -----------------------------------------------------------------------------
+
+use std::fmt;
+use std::rc::Rc;
+
+use super::exec::{Interpreter, InterpreterResult, backtrace, ps_error};
+use super::tokenizer::Token;
+use super::value::format_value;
+
+// A place where the debugger stops.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Breakpoint
+{
+    // Every time the name is executed, a procedure or an operator.
+    Name(String),
+
+    // The first token run on a line, in the named source or in any.
+    Line
+    {
+        source: Option<String>,
+        line: u32,
+    },
+}
+
+impl Breakpoint
+{
+    // Reads a breakpoint the way the break command takes it: a line
+    // number, source:line, or a name.
+    pub fn parse(text: &str) -> Option<Self>
+    {
+        if text.is_empty() || text.contains(char::is_whitespace)
+        {
+            return None;
+        }
+
+        if let Ok(line) = text.parse::<u32>()
+        {
+            return Some(Breakpoint::Line { source: None, line });
+        }
+
+        if let Some((source, line)) = text.rsplit_once(':')
+        {
+            if let Ok(line) = line.parse::<u32>()
+            {
+                return Some(Breakpoint::Line { source: Some(source.to_string()), line });
+            }
+        }
+
+        Some(Breakpoint::Name(text.to_string()))
+    }
+}
+
+impl fmt::Display for Breakpoint
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Breakpoint::Name(name) => write!(f, "{}", name),
+            Breakpoint::Line { source: Some(source), line } => write!(f, "{}:{}", source, line),
+            Breakpoint::Line { source: None, line } => write!(f, "line {}", line),
+        }
+    }
+}
+
+// Where the debugger stops next besides the breakpoints. The depths are
+// procedure nesting depths, as in Interpreter::call_depth.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StepMode
+{
+    // Only at breakpoints.
+    Continue,
+
+    // At the next token, inside a procedure the current one calls too.
+    Into,
+
+    // At the next token that is no deeper than depth, so procedures
+    // called in between run without stopping.
+    Over(usize),
+
+    // At the next token shallower than depth, once the procedure running
+    // now has returned.
+    Out(usize),
+}
+
+// Where the debugger reads commands from and shows what it has to say.
+// The REPL reads from the terminal, tests give a list of commands.
+pub trait DebugConsole
+{
+    // Shows the prompt and reads one command, None at end of input.
+    fn read_command(&mut self, prompt: &str) -> Option<String>;
+
+    // Shows some text to the user.
+    fn show(&mut self, text: &str);
+}
+
+// Commands understood while stopped, shown by help.
+pub const DEBUG_COMMANDS: &[(&str, &str)] =
+&[
+    ("step, s", "run one token, stopping inside procedures it calls"),
+    ("next, n", "run one token, running procedures it calls to the end"),
+    ("out, o", "run until the current procedure returns"),
+    ("continue, c", "run until the next breakpoint"),
+    ("stack", "show the operand stack, top first"),
+    ("dicts", "show the dictionary stack, top first"),
+    ("where", "show the token about to run"),
+    ("backtrace, bt", "show the operators and procedures running, innermost first"),
+    ("break [name|line|file:line]", "list breakpoints or add one"),
+    ("delete n", "remove breakpoint n"),
+    ("quit", "stop the program with an interrupt error"),
+    ("help", "show this list"),
+];
+
+// The prompt shown while stopped.
+const DEBUG_PROMPT: &str = "(debug) ";
+
+// The state of the debugger: its breakpoints, how far to run before
+// stopping again, and the console it talks to.
+pub struct Debugger
+{
+    pub breakpoints: Vec<Breakpoint>,
+    pub mode: StepMode,
+    console: Box<dyn DebugConsole>,
+
+    // The source and line of the token before the current one, so a
+    // line breakpoint stops once when the line is reached rather than
+    // at every token on it.
+    last_line: Option<(Rc<str>, u32)>,
+
+    // The last step command, which an empty command repeats.
+    last_step: String,
+}
+
+impl Debugger
+{
+    // A debugger with no breakpoints that only stops at breakpoints.
+    pub fn new(console: Box<dyn DebugConsole>) -> Self
+    {
+        Self
+        {
+            breakpoints: Vec::new(),
+            mode: StepMode::Continue,
+            console,
+            last_line: None,
+            last_step: "step".to_string(),
+        }
+    }
+
+    // Adds a breakpoint and describes it.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> String
+    {
+        self.breakpoints.push(breakpoint);
+        format!("breakpoint {} at {}", self.breakpoints.len(), self.breakpoints[self.breakpoints.len() - 1])
+    }
+
+    // Removes a breakpoint by its number as listed, counted from 1.
+    pub fn delete_breakpoint(&mut self, number: &str) -> String
+    {
+        match number.parse::<usize>()
+        {
+            Ok(index) if index >= 1 && index <= self.breakpoints.len() =>
+            {
+                let removed = self.breakpoints.remove(index - 1);
+                format!("deleted breakpoint {} at {}", index, removed)
+            }
+            _ => format!("Error: no breakpoint '{}'", number),
+        }
+    }
+
+    // The breakpoints, numbered.
+    pub fn breakpoint_listing(&self) -> String
+    {
+        if self.breakpoints.is_empty()
+        {
+            return "no breakpoints".to_string();
+        }
+
+        self.breakpoints.iter()
+            .enumerate()
+            .map(|(index, breakpoint)| format!("{} {}", index + 1, breakpoint))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // Decides whether to stop before token, which runs depth procedures
+    // deep. Returns the number of the breakpoint hit, 0 for a step.
+    fn stop_at(&mut self, token: &Token, depth: usize) -> Option<usize>
+    {
+        let position = token.position();
+        let new_line = self.last_line.as_ref().is_none_or(|(source, line)| *source != position.source || *line != position.line);
+        self.last_line = Some((Rc::clone(&position.source), position.line));
+
+        let hit = self.breakpoints.iter().position(|breakpoint| match breakpoint
+        {
+            Breakpoint::Name(name) => matches!(token, Token::ExecName(executed, _) if executed == name),
+            Breakpoint::Line { source, line } =>
+                new_line && position.line == *line && source.as_ref().is_none_or(|source| **source == *position.source),
+        });
+
+        if let Some(index) = hit
+        {
+            return Some(index + 1);
+        }
+
+        let step = match self.mode
+        {
+            StepMode::Continue => false,
+            StepMode::Into => true,
+            StepMode::Over(over) => depth <= over,
+            StepMode::Out(out) => depth < out,
+        };
+
+        step.then_some(0)
+    }
+}
+
+impl Interpreter
+{
+    // Called by exec_tokens before each token while a debugger is set.
+    // Stops when the debugger says so and reads commands until one of
+    // them runs the program on. The quit command fails with interrupt.
+    pub fn debug_stop(&mut self, token: &Token) -> InterpreterResult
+    {
+        let mut debugger = match self.host.debugger.take()
+        {
+            Some(debugger) => debugger,
+            None => return Ok(()),
+        };
+
+        let result = match debugger.stop_at(token, self.call_depth)
+        {
+            Some(breakpoint) =>
+            {
+                let place = describe_token(token, self.call_depth);
+                match breakpoint
+                {
+                    0 => debugger.console.show(&place),
+                    number => debugger.console.show(&format!("breakpoint {}, {}", number, place)),
+                }
+
+                self.debug_commands(&mut debugger, token)
+            }
+            None => Ok(()),
+        };
+
+        self.host.debugger = Some(debugger);
+        result
+    }
+
+    // Reads and runs commands while stopped before token.
+    fn debug_commands(&mut self, debugger: &mut Debugger, token: &Token) -> InterpreterResult
+    {
+        loop
+        {
+            // End of input lets the program finish.
+            let line = match debugger.console.read_command(DEBUG_PROMPT)
+            {
+                Some(line) => line,
+                None =>
+                {
+                    debugger.mode = StepMode::Continue;
+                    return Ok(());
+                }
+            };
+
+            let line = match line.trim()
+            {
+                "" => debugger.last_step.clone(),
+                line => line.to_string(),
+            };
+
+            let (command, argument) = match line.split_once(char::is_whitespace)
+            {
+                Some((command, argument)) => (command, argument.trim()),
+                None => (line.as_str(), ""),
+            };
+
+            let mode = match command
+            {
+                "step" | "s" => Some(StepMode::Into),
+                "next" | "n" => Some(StepMode::Over(self.call_depth)),
+                "out" | "o" => Some(StepMode::Out(self.call_depth)),
+                "continue" | "c" => Some(StepMode::Continue),
+                _ => None,
+            };
+
+            if let Some(mode) = mode
+            {
+                debugger.mode = mode;
+                debugger.last_step = command.to_string();
+                return Ok(());
+            }
+
+            let reply = match command
+            {
+                "stack" if self.is_empty() => "(empty stack)".to_string(),
+                "stack" => self.stack_listing(),
+                "dicts" => self.dict_listing(),
+                "where" => describe_token(token, self.call_depth),
+                "backtrace" | "bt" => backtrace(&self.call_stack, token.position()),
+                "break" if argument.is_empty() => debugger.breakpoint_listing(),
+                "break" => match Breakpoint::parse(argument)
+                {
+                    Some(breakpoint) => debugger.add_breakpoint(breakpoint),
+                    None => format!("Error: can not break at '{}'", argument),
+                },
+                "delete" => debugger.delete_breakpoint(argument),
+                "quit" | "q" =>
+                {
+                    debugger.mode = StepMode::Continue;
+                    return Err(ps_error("interrupt", "debugger"));
+                }
+                "help" | "h" => DEBUG_COMMANDS.iter()
+                    .map(|(usage, description)| format!("{:<28} {}", usage, description))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+                _ => format!("Error: unknown debugger command '{}', try help", command),
+            };
+
+            debugger.console.show(&reply);
+        }
+    }
+}
+
+// The token about to run, where it came from and how deep it is, for
+// example "at f (script.ps:3:7), depth 1".
+fn describe_token(token: &Token, depth: usize) -> String
+{
+    let text = match token
+    {
+        Token::Literal(value, _) => format_value(value),
+        Token::ExecName(name, _) => name.clone(),
+    };
+
+    format!("at {} ({}), depth {}", text, token.position(), depth)
+}
@@ -7,15 +7,142 @@
 // methods.
 // -----------------------------------------------------------------------------
 
-use std::collections::HashMap;
 use std::cell::RefCell;
 use std::rc::Rc;
 
-use super::value::Value;
+use super::pmap::PersistentMap;
+use super::value::{Access, Value};
+use super::vm::next_serial;
 
-pub type Dict = Rc<RefCell<HashMap<String, Value>>>;
+pub type Dict = Rc<RefCell<PsDict>>;
 pub type EnvRef = Rc<RefCell<Vec<Dict>>>;
 
+// The contents of a dictionary. Unlike strings and procedures, the access
+// attribute of a dictionary belongs to the dictionary itself, so making a
+// dictionary read only affects every reference to it.
+#[derive(Clone, Debug)]
+pub struct PsDict
+{
+    // The name to value bindings. The map is persistent, so copying a
+    // dictionary (for a lexical capture or a save) is O(1) and the copy
+    // shares structure with the original.
+    entries: PersistentMap<Value>,
+
+    // Access attribute shared by every reference to this dictionary.
+    access: Access,
+
+    // Allocation serial number, save and restore use it to tell
+    // dictionaries made before a save from ones made after it.
+    serial: u64,
+
+    // True if the dictionary lives in global VM, outside save and restore.
+    global: bool,
+
+    // Serial of the last save that journaled the dictionary, 0 if none has.
+    saved_at: u64,
+}
+
+impl PsDict
+{
+    // Creates an empty dictionary in local VM with unlimited access.
+    pub fn new() -> Self
+    {
+        Self::allocate(false)
+    }
+
+    // Creates an empty dictionary in global VM if global is true and in
+    // local VM otherwise.
+    pub fn allocate(global: bool) -> Self
+    {
+        Self
+        {
+            entries: PersistentMap::new(),
+            access: Access::Unlimited,
+            serial: next_serial(),
+            global,
+            saved_at: 0,
+        }
+    }
+
+    // Looks up the value bound to name.
+    pub fn get(&self, name: &str) -> Option<&Value>
+    {
+        self.entries.get(name)
+    }
+
+    // Binds name to value, replacing any earlier binding.
+    pub fn insert(&mut self, name: String, value: Value)
+    {
+        self.entries.insert(name, value);
+    }
+
+    // Number of bindings in the dictionary.
+    pub fn len(&self) -> usize
+    {
+        self.entries.len()
+    }
+
+    // True when the dictionary has no bindings.
+    pub fn is_empty(&self) -> bool
+    {
+        self.entries.is_empty()
+    }
+
+    // Iterates over every binding in the dictionary.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Value)>
+    {
+        self.entries.iter()
+    }
+
+    // Allocation serial number of the dictionary.
+    pub fn serial(&self) -> u64
+    {
+        self.serial
+    }
+
+    // True if the dictionary was allocated in global VM.
+    pub fn is_global(&self) -> bool
+    {
+        self.global
+    }
+
+    // Serial of the last save that journaled the dictionary.
+    pub fn saved_at(&self) -> u64
+    {
+        self.saved_at
+    }
+
+    // Marks the dictionary as journaled by the given save.
+    pub fn set_saved_at(&mut self, serial: u64)
+    {
+        self.saved_at = serial;
+    }
+
+    // The access attribute of the dictionary.
+    pub fn access(&self) -> Access
+    {
+        self.access
+    }
+
+    // Changes the access attribute of the dictionary.
+    pub fn set_access(&mut self, access: Access)
+    {
+        self.access = access;
+    }
+}
+
+impl Default for PsDict
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+// Number of dictionaries at the bottom of the stack that end can not
+// remove: globaldict and the user dictionary above it.
+pub const PERMANENT_FRAMES: usize = 2;
+
 #[derive(Debug)]
 pub struct DictStack
 {
@@ -27,8 +154,13 @@ impl DictStack
     pub fn new() -> Self
     {
 
+        // Create globaldict, which lives in global VM so save and restore
+        // leave it alone, and wrap it in a Rc<RefCell>>
+        let global_vm_dict: Dict = Rc::new(RefCell::new(PsDict::allocate(true)));
+
         // Create an empty global dictionary and wrap it in a Rc<RefCell>>
-        let global_dict: Dict = Rc::new(RefCell::new(HashMap::new()));
+        // This is the user dictionary where top level definitions go.
+        let global_dict: Dict = Rc::new(RefCell::new(PsDict::new()));
 
         // Probably the most complicated piece of code.
         // Breaking it down in pieces here is what it does:
@@ -42,7 +174,7 @@ impl DictStack
         // multiple parts of the interpreter can share the environment, for example
         // the main interpreter, or the procedures that capture this environment
         // for lexical scoping.
-        let env: EnvRef = Rc::new(RefCell::new(vec![global_dict]));
+        let env: EnvRef = Rc::new(RefCell::new(vec![global_vm_dict, global_dict]));
         Self { stack: env }
     }
 
@@ -57,6 +189,63 @@ impl DictStack
         Rc::clone(&self.stack)
     }
 
+    // Copies a dictionary stack into a new EnvRef. This is how a lexical
+    // procedure freezes the environment it was created in: later
+    // definitions in the live dictionaries do not show up in the copy.
+    // Each dictionary copy shares its persistent map with the original,
+    // so capturing costs O(depth of the stack), not O(number of names).
+    pub fn capture(env: &EnvRef) -> EnvRef
+    {
+        // Make sure the borrow does NOT live past this inner block.
+        let snapshot_vec: Vec<Dict> =
+        {
+            let env_borrow = env.borrow();
+
+            let mut snapshot = Vec::new();
+            for dict_ref in env_borrow.iter()
+            {
+                // &PsDict
+                let dict = dict_ref.borrow();
+
+                // copy the dictionary, the map inside is shared
+                let cloned_map = dict.clone();
+
+                // wrap in a new Rc<RefCell<_>>
+                let new_dict: Dict = Rc::new(RefCell::new(cloned_map));
+                snapshot.push(new_dict);
+            }
+
+            snapshot   // <- returned out of the block
+        }; // <- env_borrow is dropped RIGHT HERE
+
+        // Wrap the cloned vec in a new EnvRef
+        Rc::new(RefCell::new(snapshot_vec))
+    }
+
+    // Returns globaldict, the permanent dictionary at the bottom of the stack.
+    pub fn globaldict(&self) -> Dict
+    {
+        Rc::clone(&self.stack.borrow()[0])
+    }
+
+    // Returns the dictionary on top of the stack, the one def writes into.
+    pub fn current(&self) -> Dict
+    {
+        Rc::clone(self.stack.borrow().last().unwrap())
+    }
+
+    // Copies the list of dictionaries currently on the stack, bottom first.
+    pub fn frames(&self) -> Vec<Dict>
+    {
+        self.stack.borrow().clone()
+    }
+
+    // Replaces the list of dictionaries on the stack, used by restore.
+    pub fn set_frames(&self, frames: Vec<Dict>)
+    {
+        *self.stack.borrow_mut() = frames;
+    }
+
     // Inserts a variable definition into the top dictionary on the stack.
     // Equivalent to doing something like /x 10 def in postscript.
     // DictStack itself is immutably borrowed, but we can 
@@ -91,7 +280,7 @@ impl DictStack
             if let Some(v) = dict.get(name)
             {
                 // Clone the value so we return an owned copy.
-                return Some(v.clone());
+                return Some(v.clone().retrieved());
             }
         }
         None
@@ -109,9 +298,17 @@ impl DictStack
 
             if let Some(v) = dict.get(name)
             {
-                return Some(v.clone());
+                return Some(v.clone().retrieved());
             }
         }
         None
     }
 }
+
+impl Default for DictStack
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
@@ -0,0 +1,92 @@
+// -----------------------------------------------------------------------------
+// File: error.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Defines the error every operator and the interpreter itself fail with,
+// which is written the way PostScript reports errors.
+// -----------------------------------------------------------------------------
+
+use std::fmt;
+
+// A PostScript error such as /typecheck in --add--.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PsError
+{
+    // The name of the error, for example typecheck or undefined.
+    pub name: String,
+
+    // What was executing when the error happened, written the way the
+    // message shows it: --add-- for an operator, the name itself for an
+    // undefined name. None until the interpreter knows which one it was.
+    pub command: Option<String>,
+
+    // More about what went wrong, such as what the scanner did not like.
+    pub detail: Option<String>,
+}
+
+impl PsError
+{
+    // An error that does not know yet what was executing, such as a stack
+    // underflow found by the operand stack.
+    pub fn new(name: &str) -> Self
+    {
+        Self
+        {
+            name: name.to_string(),
+            command: None,
+            detail: None,
+        }
+    }
+
+    // The same error, raised by the given command.
+    pub fn in_command(self, command: String) -> Self
+    {
+        Self
+        {
+            command: Some(command),
+            ..self
+        }
+    }
+
+    // The same error with an explanation after it.
+    pub fn with_detail(self, detail: String) -> Self
+    {
+        Self
+        {
+            detail: Some(detail),
+            ..self
+        }
+    }
+}
+
+// For example "/typecheck in --add--" or "/syntaxerror in --scanner--:
+// Unmatched '{' in procedure literal".
+impl fmt::Display for PsError
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "/{}", self.name)?;
+
+        if let Some(command) = &self.command
+        {
+            write!(f, " in {}", command)?;
+        }
+
+        if let Some(detail) = &self.detail
+        {
+            write!(f, ": {}", detail)?;
+        }
+
+        Ok(())
+    }
+}
+
+// An error equals the message it is written as.
+impl PartialEq<&str> for PsError
+{
+    fn eq(&self, other: &&str) -> bool
+    {
+        format!("{}", self) == *other
+    }
+}
@@ -7,13 +7,57 @@
 // name resolution, scoping rules (dynamic & lexical), and procedure calls.
 // -----------------------------------------------------------------------------
 
+use std::io::Write;
+use std::rc::Rc;
+
 use super::stack::OperandStack;
-use super::dict::{DictStack, EnvRef};
-use super::tokenizer::{Token, tokenize};
-use super::value::Value;
-use super::scope::ScopeMode;
+use super::dict::{Dict, DictStack, EnvRef, PERMANENT_FRAMES};
+use super::tokenizer::{Position, Token, tokenize_in};
+use super::value::{Procedure, Value, format_value};
+use super::scope::{Activation, ScopeMode};
+use super::vm::VirtualMemory;
+use super::output::{OutputBuffer, OutputSink};
+use super::log::LogLevel;
+use super::host::Host;
+use super::error::PsError;
+
+pub type InterpreterResult = Result<(), PsError>;
+
+// How many values from the top of the operand stack the execution trace
+// shows before and after each token.
+pub const TRACE_STACK_ENTRIES: usize = 3;
+
+// Builds the error an operator fails with, for example
+// /typecheck in --cvi--.
+pub fn ps_error(error_name: &str, operator: &str) -> PsError
+{
+    PsError::new(error_name).in_command(format!("--{}--", operator))
+}
 
-pub type InterpreterResult = Result<(), String>;
+// A builtin operator or named procedure that is running and the position
+// of the name that called it.
+#[derive(Clone, Debug)]
+pub struct Call
+{
+    pub name: String,
+    pub position: Position,
+
+    // True for a builtin operator, which is shown as --name--.
+    pub operator: bool,
+}
+
+impl Call
+{
+    // The name the way backtraces show it.
+    pub fn label(&self) -> String
+    {
+        match self.operator
+        {
+            true => format!("--{}--", self.name),
+            false => self.name.clone(),
+        }
+    }
+}
 
 // Defines the interpreter structure.
 pub struct Interpreter
@@ -24,27 +68,116 @@ pub struct Interpreter
     // The dictionary stack that the interpreter uses.
     pub dict: DictStack,
 
-    // The type of scoping the interpreter uses.
+    // The type of scoping the interpreter uses for top level code.
     pub scope_mode: ScopeMode,
+
+    // The scoping mode of the procedure currently running, None at the
+    // top level.
+    pub procedure_scope: Option<ScopeMode>,
+
+    // Snapshots taken by save that restore can return to.
+    pub vm: VirtualMemory,
+
+    // The lexical procedure call whose frames def, begin and end work on,
+    // None at the top level.
+    pub activation: Option<Activation>,
+
+    // The builtin operators and named procedures running now, outermost
+    // first.
+    pub call_stack: Vec<Call>,
+
+    // The calls that were running when the last error happened,
+    // outermost first, down to the operator that failed, and the position
+    // execution had reached in the innermost of them.
+    pub error_calls: Vec<Call>,
+    pub error_position: Option<Position>,
+
+    // How many procedure bodies are running inside one another.
+    pub call_depth: usize,
+
+    // The output, log, tracing, debugger and profiler the host chose.
+    pub host: Host,
 }
 
 impl Interpreter
 {
     // Interpreter constructor.
     pub fn new(scope: ScopeMode) -> Self 
+    {
+        Self::with_host(scope, Host::new())
+    }
+
+    // An interpreter with nothing defined that keeps the given host
+    // settings.
+    pub fn with_host(scope: ScopeMode, host: Host) -> Self
     {
         Self
         {
             opstack: OperandStack::new(),
             dict: DictStack::new(),
             scope_mode: scope,
+            procedure_scope: None,
+            vm: VirtualMemory::new(),
+            activation: None,
+            call_stack: Vec::new(),
+            error_calls: Vec::new(),
+            error_position: None,
+            call_depth: 0,
+            host,
         }
     }
 
+    // Throws away the stacks, the dictionaries and everything saved and
+    // starts again in the same scoping mode with the same host settings.
+    pub fn reset(&mut self)
+    {
+        let host = std::mem::take(&mut self.host);
+        *self = Self::with_host(self.scope_mode, host);
+    }
+
+    // Sends all further output to sink.
+    pub fn set_output(&mut self, sink: OutputSink)
+    {
+        self.host.output = sink;
+    }
+
+    // Sends all further output to a new in-memory buffer and returns it,
+    // so the output can be read back as a String.
+    pub fn capture_output(&mut self) -> OutputBuffer
+    {
+        let buffer = OutputBuffer::new();
+        self.host.output = Box::new(buffer.clone());
+        buffer
+    }
+
+    // Writes bytes to the output, an ioerror in operator if that fails.
+    pub fn write_output(&mut self, bytes: &[u8], operator: &str) -> InterpreterResult
+    {
+        self.host.output.write_all(bytes).map_err(|_| ps_error("ioerror", operator))
+    }
+
+    // Writes a line of text and a newline to the output.
+    pub fn write_line(&mut self, text: &str, operator: &str) -> InterpreterResult
+    {
+        self.write_output(format!("{}\n", text).as_bytes(), operator)
+    }
+
     // Tokenizes the input and executes those tokens.
     pub fn interpret(&mut self, src: &str) -> InterpreterResult 
     {
-        let tokens = tokenize(src)?;
+        self.interpret_source(src.as_bytes(), "-")
+    }
+
+    // Same as interpret, with positions in the code naming the source it
+    // came from, such as the path of a file. The source is read as bytes,
+    // so string literals keep bytes that are not valid UTF-8.
+    pub fn interpret_source(&mut self, src: &[u8], source: &str) -> InterpreterResult
+    {
+        self.error_calls.clear();
+        self.error_position = None;
+
+        let tokens = tokenize_in(src, self.vm.global, source)
+            .map_err(|e| ps_error("syntaxerror", "scanner").with_detail(e))?;
         self.exec_tokens(&tokens, None)
         
     }
@@ -55,67 +188,391 @@ impl Interpreter
     {
         for token in tokens
         {
-            match token
+            let mut result = Ok(());
+            if self.host.debugger.is_some()
+            {
+                result = self.debug_stop(token);
+            }
+
+            if result.is_ok()
+            {
+                result = if self.host.exec_trace
+                {
+                    self.exec_token_traced(token, defining_env.as_ref())
+                }
+                else
+                {
+                    self.exec_token(token, defining_env.as_ref())
+                };
+            }
+
+            // Errors that did not come from an operator, such as an
+            // undefined name, happened at the token itself.
+            if let Err(e) = result
+            {
+                self.record_error(token.position());
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    // Executes a single token.
+    fn exec_token(&mut self, token: &Token, defining_env: Option<&EnvRef>) -> InterpreterResult
+    {
+        match token
+        {
+            // In lexical mode a procedure literal captures the environment
+            // it is evaluated in: the procedure currently running, or the
+            // live dictionary stack at the top level. This way procedures
+            // handed to if, repeat or for, and procedures nested in other
+            // procedures, see the bindings around them.
+            Token::Literal(Value::Procedure(procedure), _) if self.current_scope() == ScopeMode::Lexical && procedure.env.is_none() =>
             {
-                // If token is a number, variable name, boolean value, string, or procedure body
-                // simply push it onto the stack. 
-                Token::Literal(v) => self.opstack.push(v.clone()),
+                let captured = self.capture(procedure, defining_env);
+                self.opstack.push(Value::Procedure(captured));
+            }
+
+            // If token is a number, variable name, boolean value, string, or procedure body
+            // simply push it onto the stack. 
+            Token::Literal(v, _) => self.opstack.push(v.clone()),
 
-                // Otherwise the token is an executable and needs to be resolved.
-                Token::ExecName(name) =>
+            // Otherwise the token is an executable and needs to be resolved.
+            Token::ExecName(name, position) =>
+            {
+                // The name runs as an operator if it is one, so it is on
+                // the call stack while it does.
+                self.call_stack.push(Call { name: name.clone(), position: position.clone(), operator: true });
+                let watch = self.opstack.watch();
+                let result = self.profiled_builtin(name);
+
+                if let Err(mut e) = result
                 {
-                    if self.try_builtin(name)?
+                    // An operator that fails itself, rather than in a
+                    // procedure it ran, leaves its operands on the stack
+                    // so the error report shows them.
+                    if self.error_position.is_none()
                     {
-                        // Executed the token, move to the next one.
-                        continue;
+                        self.opstack.restore(watch);
                     }
+                    else
+                    {
+                        self.opstack.release(watch);
+                    }
+
+                    // Errors that do not say which operator failed, such
+                    // as a stack underflow, get this one.
+                    self.record_error(position);
+                    self.call_stack.pop();
+                    e.command.get_or_insert_with(|| format!("--{}--", name));
+                    return Err(e);
+                }
+
+                self.call_stack.pop();
+                self.opstack.release(watch);
+                if matches!(result, Ok(true))
+                {
+                    return Ok(());
+                }
+
+                let resolved = match self.current_scope()
+                {
+                    // Lookup for value in dict dynamically.
+                    ScopeMode::Dynamic => self.dict.lookup_dynamic(name),
 
-                    let resolved = match self.scope_mode
+                    // Lookup for value in dict lexically, falling back
+                    // to the live stack for names the environment does
+                    // not bind (recursion and forward references).
+                    ScopeMode::Lexical => match defining_env
                     {
-                        // Lookup for value in dict dynamically.
-                        ScopeMode::Dynamic => self.dict.lookup_dynamic(name),
+                        Some(env) => self.dict.lookup_lexical(name, env)
+                            .or_else(|| self.dict.lookup_dynamic(name)),
+                        None => self.dict.lookup_dynamic(name),
+                    },
+                }.ok_or_else(|| PsError::new("undefined").in_command(name.clone()))?;
 
-                        // Lookup for value in dict lexically.
-                        ScopeMode::Lexical =>
+                match resolved
+                {
+                    Value::Procedure(procedure) =>
+                    {
+                        // Value is a procedure that needs to be executed.
+                        // Recursively call the function using the procedure body.
+                        self.host.log.message(LogLevel::Trace, || format!("call {} ({})", name, procedure.scope_mode().name()));
+                        self.call_stack.push(Call { name: name.clone(), position: position.clone(), operator: false });
+                        let result = match &mut self.host.profiler
                         {
-                            if let Some(env) = &defining_env
-                            {
-                                self.dict.lookup_lexical(name, env)
-                            }
-                            else
+                            Some(profiler) =>
                             {
-                                let global_env = self.dict.env();
-                                self.dict.lookup_lexical(name, &global_env)
+                                profiler.enter(name.clone());
+                                let result = self.invoke_procedure(&procedure);
+                                if let Some(profiler) = &mut self.host.profiler
+                                {
+                                    profiler.exit();
+                                }
+                                result
                             }
-                        }
-                    }.ok_or_else(|| format!("Undefined name: {}", name))?;
+                            None => self.invoke_procedure(&procedure),
+                        };
+                        self.call_stack.pop();
 
-                    match resolved
-                    {
-                        Value::Procedure(body, captured_env) =>
+                        if let Err(e) = result
                         {
-                            // Value is a procedure that needs to be executed.
-                            // Recursively call the function using the procedure body.
-                            self.exec_tokens(&body, captured_env)?;
+                            self.host.log.message(LogLevel::Trace, || format!("unwind {}: {}", name, e));
+                            return Err(e);
                         }
-
-                        // Otherwise push the value to the stack if its
-                        // not a procedure.
-                        _ => self.opstack.push(resolved),
+                        self.host.log.message(LogLevel::Trace, || format!("return from {}", name));
                     }
+
+                    // An executable name bound to a name runs in its place.
+                    Value::ExecName(inner) =>
+                        self.exec_tokens(&[Token::ExecName(inner, position.clone())], defining_env.cloned())?,
+
+                    // Otherwise push the value to the stack if its
+                    // not a procedure.
+                    _ => self.opstack.push(resolved),
                 }
             }
         }
         Ok(())
     }
 
+    // Executes a token and writes two lines about it to the log, both
+    // with the procedure nesting depth and the token. The first is
+    // written when the token starts and shows the top of the operand
+    // stack before it, the second when it is done and shows the top of
+    // the stack after it (or the error it failed with). The lines of a
+    // procedure's body come between the two lines of the name that
+    // called it.
+    fn exec_token_traced(&mut self, token: &Token, defining_env: Option<&EnvRef>) -> InterpreterResult
+    {
+        let prefix = format!("[exec] {}{}", "  ".repeat(self.call_depth), self.call_depth);
+        let text = match token
+        {
+            Token::Literal(value, _) => format_value(value),
+            Token::ExecName(name, _) => name.clone(),
+        };
+
+        self.host.log.write_line(&format!("{} {}  {}", prefix, text, self.stack_top()));
+        let result = self.exec_token(token, defining_env);
+
+        let outcome = match &result
+        {
+            Ok(()) => self.stack_top(),
+            Err(e) => e.to_string(),
+        };
+
+        self.host.log.write_line(&format!("{} {}  -> {}", prefix, text, outcome));
+        result
+    }
+
+    // The top TRACE_STACK_ENTRIES values of the operand stack in ==
+    // form, bottom first, for example [.. 2 3 4].
+    fn stack_top(&self) -> String
+    {
+        let mut entries: Vec<String> = self.opstack.top(TRACE_STACK_ENTRIES).iter().map(format_value).collect();
+        if self.opstack.len() > TRACE_STACK_ENTRIES
+        {
+            entries.insert(0, "..".to_string());
+        }
+
+        format!("[{}]", entries.join(" "))
+    }
+
+    // Runs try_builtin, timing the operator when the profiler is on.
+    fn profiled_builtin(&mut self, name: &str) -> Result<bool, PsError>
+    {
+        let profiler = match &mut self.host.profiler
+        {
+            Some(profiler) => profiler,
+            None => return self.try_builtin(name),
+        };
+
+        profiler.enter(format!("--{}--", name));
+        let result = self.try_builtin(name);
+
+        if let Some(profiler) = &mut self.host.profiler
+        {
+            match result
+            {
+                Ok(false) => profiler.cancel(),
+                _ => profiler.exit(),
+            }
+        }
+
+        result
+    }
+
+    // Runs a procedure called by name. A lexical procedure gets a new
+    // activation for its local definitions, see scope.rs.
+    pub fn invoke_procedure(&mut self, procedure: &Procedure) -> InterpreterResult
+    {
+        match &procedure.env
+        {
+            Some(env) =>
+            {
+                let activation = Activation::enter(env);
+                let frames = Rc::clone(&activation.frames);
+                self.run_body(procedure, Some(activation), Some(frames))
+            }
+            None => self.call_procedure(procedure),
+        }
+    }
+
+    // Runs a procedure object in place, the way control operators do: with
+    // the environment it captured, defining names in the activation it was
+    // captured in. Procedures whose access does not allow execution are
+    // rejected.
+    pub fn call_procedure(&mut self, procedure: &Procedure) -> InterpreterResult
+    {
+        self.run_body(procedure, procedure.home.clone(), procedure.env.clone())
+    }
+
+    // Executes a procedure body inside the given activation, putting the
+    // previous activation back afterwards even if the body fails.
+    fn run_body(&mut self, procedure: &Procedure, activation: Option<Activation>, env: Option<EnvRef>) -> InterpreterResult
+    {
+        if !procedure.access.can_execute()
+        {
+            return Err(ps_error("invalidaccess", "exec"));
+        }
+
+        let caller = std::mem::replace(&mut self.activation, activation);
+        let caller_scope = self.procedure_scope.replace(procedure.scope_mode());
+        self.call_depth += 1;
+        let result = self.exec_tokens(&procedure.body, env);
+        self.call_depth -= 1;
+        self.activation = caller;
+        self.procedure_scope = caller_scope;
+
+        result
+    }
+
+    // The scoping mode of the code being executed: the mode of the running
+    // procedure, or the interpreter's mode at the top level.
+    pub fn current_scope(&self) -> ScopeMode
+    {
+        self.procedure_scope.unwrap_or(self.scope_mode)
+    }
+
+    // Gives a procedure the environment it is created in. Inside a lexical
+    // call that is the call's activation, shared so the procedure sees
+    // later local definitions. Otherwise it is a snapshot of defining_env,
+    // or of the live dictionary stack when there is none.
+    pub fn capture(&self, procedure: &Procedure, defining_env: Option<&EnvRef>) -> Procedure
+    {
+        match &self.activation
+        {
+            Some(activation) => Procedure
+            {
+                env: Some(Rc::clone(&activation.frames)),
+                home: Some(activation.clone()),
+                ..procedure.clone()
+            },
+            None => Procedure
+            {
+                env: Some(DictStack::capture(defining_env.unwrap_or(&self.dict.env()))),
+                home: None,
+                ..procedure.clone()
+            },
+        }
+    }
+
+    // The dictionary stack def, begin and end work on: the running
+    // activation, or the live stack at the top level. Also returns how
+    // many dictionaries at its bottom end may not remove.
+    pub fn definition_frames(&self) -> (EnvRef, usize)
+    {
+        match &self.activation
+        {
+            Some(activation) => (Rc::clone(&activation.frames), activation.base),
+            None => (self.dict.env(), PERMANENT_FRAMES),
+        }
+    }
+
+    // The dictionary def writes into.
+    pub fn current_dict(&self) -> Dict
+    {
+        match &self.activation
+        {
+            Some(activation) => activation.current(),
+            None => self.dict.current(),
+        }
+    }
+
+    // The first token to fail records the calls running and the position
+    // it had reached, the callers it unwinds through leave them alone.
+    fn record_error(&mut self, position: &Position)
+    {
+        if self.error_position.is_none()
+        {
+            self.error_position = Some(position.clone());
+            self.error_calls = self.call_stack.clone();
+        }
+    }
+
+    // Describes an error the way Ghostscript does: the error and where it
+    // happened, then the operand stack (bottom first) as it was when it
+    // happened and the operators and procedures that were running.
+    pub fn error_report(&self, error: &PsError) -> String
+    {
+        let operands: Vec<String> = self.opstack.snapshot().iter().map(format_value).collect();
+
+        let mut lines = vec![format!("Error: {}", error), "Operand stack:".to_string()];
+        if !operands.is_empty()
+        {
+            lines.push(format!("   {}", operands.join("   ")));
+        }
+
+        if let Some(position) = &self.error_position
+        {
+            lines.push("Backtrace:".to_string());
+            lines.push(backtrace(&self.error_calls, position));
+        }
+
+        lines.join("\n")
+    }
+
+    // The operand stack the way pstack prints it: one value per line,
+    // top of the stack first.
+    pub fn stack_listing(&self) -> String
+    {
+        self.opstack.snapshot().iter().rev().map(format_value).collect::<Vec<String>>().join("\n")
+    }
+
+    // One line per dictionary on the dictionary stack def works on, top
+    // first, with its size and the names in it.
+    pub fn dict_listing(&self) -> String
+    {
+        let (frames, _) = self.definition_frames();
+        let mut lines = Vec::new();
+
+        for (index, frame) in frames.borrow().iter().enumerate().rev()
+        {
+            let label = match index
+            {
+                0 => "globaldict",
+                1 => "userdict",
+                _ => "dict",
+            };
+
+            let dictionary = frame.borrow();
+            let mut names: Vec<&String> = dictionary.iter().map(|(name, _)| name).collect();
+            names.sort();
+
+            let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+            lines.push(format!("{} {} ({} entries) {}", index, label, dictionary.len(), names.join(" ")).trim_end().to_string());
+        }
+
+        lines.join("\n")
+    }
+
     // These methods are for convenience when executing.
     pub fn push(&mut self, v: Value)
     {
         self.opstack.push(v);
     }
 
-    pub fn pop(&mut self) -> Result<Value, String>
+    pub fn pop(&mut self) -> Result<Value, PsError>
     { 
         self.opstack.pop()
     }
@@ -131,8 +588,34 @@ impl Interpreter
     {
         self.opstack.len()
     }
+    pub fn is_empty(&self) -> bool
+    {
+        self.opstack.is_empty()
+    }
     pub fn clear(&mut self)
     {
         self.opstack.clear();
     }
-}
\ No newline at end of file
+}
+
+// One line per operator or procedure in calls, innermost first, with the
+// position it had reached: position for the innermost one, and the
+// position of the next call for each of the others. For example
+//    --mul-- at prog.ps:1:11
+//    sq at prog.ps:1:11
+//    f at prog.ps:3:3
+//    top level at -:1:3
+pub fn backtrace(calls: &[Call], position: &Position) -> String
+{
+    let mut lines = Vec::new();
+    let mut reached = position;
+
+    for call in calls.iter().rev()
+    {
+        lines.push(format!("   {} at {}", call.label(), reached));
+        reached = &call.position;
+    }
+    lines.push(format!("   top level at {}", reached));
+
+    lines.join("\n")
+}
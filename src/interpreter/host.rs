@@ -0,0 +1,60 @@
+// -----------------------------------------------------------------------------
+// File: host.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// The settings the program running the interpreter (the REPL, the command
+// line or a test) chooses for it, kept together so they can be carried
+// over when the interpreter state is thrown away and started again.
+// -----------------------------------------------------------------------------
+
+use super::debug::Debugger;
+use super::log::Log;
+use super::output::{OutputSink, stdout_sink};
+use super::profile::Profiler;
+
+// Everything about an interpreter that belongs to its host rather than to
+// the PostScript program it runs. Interpreter::reset keeps all of it.
+pub struct Host
+{
+    // Where print, =, ==, stack and pstack write, stdout unless the host
+    // sets something else.
+    pub output: OutputSink,
+
+    // Diagnostics about definitions, calls and the dictionary stack, off
+    // unless the host turns it on.
+    pub log: Log,
+
+    // Write lines to the log for every token executed (settrace).
+    pub exec_trace: bool,
+
+    // The step debugger, consulted before every token when set.
+    pub debugger: Option<Debugger>,
+
+    // Counts and times operators and procedures when set.
+    pub profiler: Option<Profiler>,
+}
+
+impl Host
+{
+    // Output to stdout, the log off and no tracing, debugger or profiler.
+    pub fn new() -> Self
+    {
+        Self
+        {
+            output: stdout_sink(),
+            log: Log::new(),
+            exec_trace: false,
+            debugger: None,
+            profiler: None,
+        }
+    }
+}
+
+impl Default for Host
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
@@ -0,0 +1,128 @@
+// -----------------------------------------------------------------------------
+// File: log.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// A diagnostics channel, separate from the program's own output, that
+// reports what the interpreter is doing: definitions, procedure calls
+// and changes to the dictionary stack.
+// -----------------------------------------------------------------------------
+
+use std::io::{self, Write};
+
+use super::output::{OutputBuffer, OutputSink};
+
+// How much the log reports, from least to most. Each level includes
+// everything the levels before it report.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel
+{
+    // Things that are probably mistakes, such as a definition no name
+    // lookup will ever find.
+    Warn,
+
+    // Definitions and changes to the dictionary stack.
+    Debug,
+
+    // Every procedure call and return as well.
+    Trace,
+}
+
+impl LogLevel
+{
+    pub fn name(self) -> &'static str
+    {
+        match self
+        {
+            LogLevel::Warn => "warn",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        match name
+        {
+            "warn" => Some(LogLevel::Warn),
+            "debug" => Some(LogLevel::Debug),
+            "trace" => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+}
+
+// The log: the level it reports up to (None when it is off) and where
+// the messages go, stderr unless the host sets something else.
+pub struct Log
+{
+    pub level: Option<LogLevel>,
+    sink: OutputSink,
+}
+
+impl Log
+{
+    // A log that is off and would write to stderr.
+    pub fn new() -> Self
+    {
+        Self
+        {
+            level: None,
+            sink: Box::new(io::stderr()),
+        }
+    }
+
+    // Reports messages of level and below from now on, None turns the
+    // log off.
+    pub fn set_level(&mut self, level: Option<LogLevel>)
+    {
+        self.level = level;
+    }
+
+    // Sends all further messages to sink.
+    pub fn set_sink(&mut self, sink: OutputSink)
+    {
+        self.sink = sink;
+    }
+
+    // Sends all further messages to a new in-memory buffer and returns it.
+    pub fn capture(&mut self) -> OutputBuffer
+    {
+        let buffer = OutputBuffer::new();
+        self.sink = Box::new(buffer.clone());
+        buffer
+    }
+
+    // True if messages of this level are reported.
+    pub fn enabled(&self, level: LogLevel) -> bool
+    {
+        self.level.is_some_and(|limit| level <= limit)
+    }
+
+    // Writes a message as a line such as "[debug] def /x 1". The text is
+    // only built when the level is enabled, so logging costs nothing
+    // while the log is off. A log that can not be written is not an
+    // error in the program, so write failures are ignored.
+    pub fn message(&mut self, level: LogLevel, text: impl FnOnce() -> String)
+    {
+        if self.enabled(level)
+        {
+            let _ = writeln!(self.sink, "[{}] {}", level.name(), text());
+        }
+    }
+
+    // Writes a line whatever the level, for output that was asked for
+    // by itself, such as the execution trace.
+    pub fn write_line(&mut self, text: &str)
+    {
+        let _ = writeln!(self.sink, "{}", text);
+    }
+}
+
+impl Default for Log
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
@@ -7,9 +7,18 @@
 // -----------------------------------------------------------------------------
 
 pub mod value;
+pub mod string;
 pub mod stack;
 pub mod dict;
+pub mod pmap;
 pub mod builtin;
 pub mod tokenizer;
 pub mod exec;
-pub mod scope;
\ No newline at end of file
+pub mod scope;
+pub mod vm;
+pub mod output;
+pub mod log;
+pub mod debug;
+pub mod profile;
+pub mod host;
+pub mod error;
@@ -0,0 +1,65 @@
+// -----------------------------------------------------------------------------
+// File: output.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Where the interpreter writes what print, =, ==, stack and pstack
+// produce. Standard output by default, but any writer can be plugged in.
+// -----------------------------------------------------------------------------
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+// The writer the interpreter sends its output to.
+pub type OutputSink = Box<dyn Write>;
+
+// The sink used when nothing else is set: the process stdout.
+pub fn stdout_sink() -> OutputSink
+{
+    Box::new(io::stdout())
+}
+
+// A sink that keeps everything written to it in memory. Clones share the
+// same buffer, so one clone can be given to the interpreter and the other
+// kept to read the output back, which is what tests do.
+#[derive(Clone, Default)]
+pub struct OutputBuffer
+{
+    bytes: Rc<RefCell<Vec<u8>>>,
+}
+
+impl OutputBuffer
+{
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    // Everything written so far, as text.
+    pub fn text(&self) -> String
+    {
+        String::from_utf8_lossy(&self.bytes.borrow()).into_owned()
+    }
+
+    // Everything written so far as text, emptying the buffer.
+    pub fn take(&self) -> String
+    {
+        let bytes = std::mem::take(&mut *self.bytes.borrow_mut());
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+impl Write for OutputBuffer
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+        self.bytes.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+// -----------------------------------------------------------------------------
+// File: pmap.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Defines PersistentMap, a string keyed map whose copies share structure,
+// so cloning one is O(1) no matter how many entries it holds.
+// -----------------------------------------------------------------------------
+
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+// Each level of the tree uses this many bits of the key's hash,
+// giving every branch 16 children.
+const BITS_PER_LEVEL: u32 = 4;
+const BRANCH_WIDTH: usize = 1 << BITS_PER_LEVEL;
+const LEVEL_MASK: u64 = (BRANCH_WIDTH as u64) - 1;
+
+// A node of the hash trie. Nodes are never changed once built: an insert
+// copies the nodes on the path from the root to the changed leaf and
+// shares everything else with the old map.
+enum Node<V>
+{
+    // Children picked by the next few bits of the hash.
+    Branch([Option<Rc<Node<V>>>; BRANCH_WIDTH]),
+
+    // Every entry whose key has this full hash. There is almost always
+    // exactly one, more only when two keys collide.
+    Leaf(u64, Vec<(String, V)>),
+}
+
+// A persistent (immutable, structurally shared) hash map from strings to V.
+// Changing a map only affects that map value; clones made earlier keep
+// seeing the old contents.
+pub struct PersistentMap<V>
+{
+    root: Option<Rc<Node<V>>>,
+    len: usize,
+}
+
+impl<V: Clone> PersistentMap<V>
+{
+    // Creates an empty map.
+    pub fn new() -> Self
+    {
+        Self
+        {
+            root: None,
+            len: 0,
+        }
+    }
+
+    // Number of entries in the map.
+    pub fn len(&self) -> usize
+    {
+        self.len
+    }
+
+    // True when the map has no entries.
+    pub fn is_empty(&self) -> bool
+    {
+        self.len == 0
+    }
+
+    // Looks up the value stored under key.
+    pub fn get(&self, key: &str) -> Option<&V>
+    {
+        let hash = hash_key(key);
+        let mut node = self.root.as_deref()?;
+        let mut shift = 0;
+
+        loop
+        {
+            match node
+            {
+                Node::Branch(children) =>
+                {
+                    node = children[slot(hash, shift)].as_deref()?;
+                    shift += BITS_PER_LEVEL;
+                }
+                Node::Leaf(leaf_hash, entries) =>
+                {
+                    if *leaf_hash != hash
+                    {
+                        return None;
+                    }
+                    return entries.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+                }
+            }
+        }
+    }
+
+    // Stores value under key, replacing any earlier value. Only the nodes
+    // on the path to the key are copied, O(log n) work.
+    pub fn insert(&mut self, key: String, value: V)
+    {
+        let hash = hash_key(&key);
+        let (root, added) = insert_into(self.root.as_ref(), hash, 0, key, value);
+
+        self.root = Some(root);
+        if added
+        {
+            self.len += 1;
+        }
+    }
+
+    // Iterates over every entry in no particular order.
+    pub fn iter(&self) -> Iter<'_, V>
+    {
+        Iter
+        {
+            pending: self.root.as_deref().into_iter().collect(),
+            current: [].iter(),
+        }
+    }
+}
+
+// Cloning only copies the root pointer, the nodes are shared.
+impl<V> Clone for PersistentMap<V>
+{
+    fn clone(&self) -> Self
+    {
+        Self
+        {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<V: Clone> Default for PersistentMap<V>
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl<V: Clone + fmt::Debug> fmt::Debug for PersistentMap<V>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// Walks the trie depth first, yielding the entries of each leaf in turn.
+pub struct Iter<'a, V>
+{
+    pending: Vec<&'a Node<V>>,
+    current: std::slice::Iter<'a, (String, V)>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V>
+{
+    type Item = (&'a String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item>
+    {
+        loop
+        {
+            if let Some((key, value)) = self.current.next()
+            {
+                return Some((key, value));
+            }
+
+            match self.pending.pop()?
+            {
+                Node::Branch(children) =>
+                {
+                    self.pending.extend(children.iter().flatten().map(|child| child.as_ref()));
+                }
+                Node::Leaf(_, entries) => self.current = entries.iter(),
+            }
+        }
+    }
+}
+
+// Hashes a key. DefaultHasher::new always uses the same keys, so the
+// layout of a map does not change between runs.
+fn hash_key(key: &str) -> u64
+{
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Which child of a branch at the given depth the hash belongs to.
+fn slot(hash: u64, shift: u32) -> usize
+{
+    ((hash >> shift) & LEVEL_MASK) as usize
+}
+
+// Returns a new node equal to node with key set to value, and whether the
+// key was not there before. The old node is left untouched.
+fn insert_into<V: Clone>(node: Option<&Rc<Node<V>>>, hash: u64, shift: u32, key: String, value: V) -> (Rc<Node<V>>, bool)
+{
+    let node = match node
+    {
+        Some(node) => node,
+        None => return (Rc::new(Node::Leaf(hash, vec![(key, value)])), true),
+    };
+
+    match node.as_ref()
+    {
+        Node::Branch(children) =>
+        {
+            let index = slot(hash, shift);
+            let (child, added) = insert_into(children[index].as_ref(), hash, shift + BITS_PER_LEVEL, key, value);
+
+            let mut children = children.clone();
+            children[index] = Some(child);
+            (Rc::new(Node::Branch(children)), added)
+        }
+
+        // Same hash: replace the entry or add to the collision bucket.
+        Node::Leaf(leaf_hash, entries) if *leaf_hash == hash =>
+        {
+            let mut entries = entries.clone();
+            let added = match entries.iter_mut().find(|(k, _)| *k == key)
+            {
+                Some(entry) =>
+                {
+                    entry.1 = value;
+                    false
+                }
+                None =>
+                {
+                    entries.push((key, value));
+                    true
+                }
+            };
+            (Rc::new(Node::Leaf(hash, entries)), added)
+        }
+
+        // Different hash: push the existing leaf one level down into a new
+        // branch, then insert into that branch.
+        Node::Leaf(leaf_hash, _) =>
+        {
+            let mut children: [Option<Rc<Node<V>>>; BRANCH_WIDTH] = Default::default();
+            children[slot(*leaf_hash, shift)] = Some(Rc::clone(node));
+
+            let branch = Rc::new(Node::Branch(children));
+            insert_into(Some(&branch), hash, shift, key, value)
+        }
+    }
+}
@@ -0,0 +1,191 @@
+// -----------------------------------------------------------------------------
+// File: profile.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Counts how often each builtin operator and each named procedure runs
+// and how much wall time it takes, for finding where a slow program
+// spends its time.
+// -----------------------------------------------------------------------------
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// What the profiler knows about one operator or procedure.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats
+{
+    // How many times it ran.
+    pub calls: u64,
+
+    // Time from start to finish, including everything it ran. Recursive
+    // calls are only counted once, by the outermost one.
+    pub total: Duration,
+
+    // Time spent in it minus the time of the operators and procedures
+    // it ran.
+    pub own: Duration,
+}
+
+// An operator or procedure that is running.
+struct Frame
+{
+    name: String,
+
+    // The stack of this frame and every frame around it, an index into
+    // Profiler::stack_nodes.
+    stack: usize,
+
+    start: Instant,
+
+    // Time taken by the operators and procedures it has run so far.
+    children: Duration,
+}
+
+// Collects Stats while the interpreter runs. Operators are named as
+// --add-- and procedures by their name, as in the execution stack of an
+// error report.
+#[derive(Default)]
+pub struct Profiler
+{
+    stats: HashMap<String, Stats>,
+
+    // Every distinct stack of frames seen so far, each one the name of
+    // its innermost frame and the index of the stack around it. Stacks
+    // are interned this way so entering a frame costs the same however
+    // deep it is; the full paths are only built for collapsed_stacks.
+    stack_nodes: Vec<(Option<usize>, String)>,
+    stack_ids: HashMap<(Option<usize>, String), usize>,
+
+    // Own time of every distinct stack of frames, by index.
+    stacks: HashMap<usize, Duration>,
+
+    frames: Vec<Frame>,
+}
+
+impl Profiler
+{
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    // Starts timing an operator or procedure.
+    pub fn enter(&mut self, name: String)
+    {
+        let key = (self.frames.last().map(|parent| parent.stack), name);
+        let stack = match self.stack_ids.get(&key)
+        {
+            Some(&stack) => stack,
+            None =>
+            {
+                self.stack_nodes.push(key.clone());
+                self.stack_ids.insert(key.clone(), self.stack_nodes.len() - 1);
+                self.stack_nodes.len() - 1
+            }
+        };
+
+        self.frames.push(Frame
+        {
+            name: key.1,
+            stack,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    // Stops timing the innermost operator or procedure and records it.
+    pub fn exit(&mut self)
+    {
+        let frame = match self.frames.pop()
+        {
+            Some(frame) => frame,
+            None => return,
+        };
+
+        let elapsed = frame.start.elapsed();
+        let own = elapsed.saturating_sub(frame.children);
+        let recursive = self.frames.iter().any(|outer| outer.name == frame.name);
+
+        if let Some(parent) = self.frames.last_mut()
+        {
+            parent.children += elapsed;
+        }
+
+        let stats = self.stats.entry(frame.name).or_default();
+        stats.calls += 1;
+        stats.own += own;
+        if !recursive
+        {
+            stats.total += elapsed;
+        }
+
+        *self.stacks.entry(frame.stack).or_default() += own;
+    }
+
+    // Forgets the innermost frame without recording it, for a name that
+    // turned out not to be an operator.
+    pub fn cancel(&mut self)
+    {
+        self.frames.pop();
+    }
+
+    // The stats of one operator (as --name--) or procedure.
+    pub fn stats(&self, name: &str) -> Option<Stats>
+    {
+        self.stats.get(name).copied()
+    }
+
+    // A table of every operator and procedure that ran, the most total
+    // time first.
+    pub fn report(&self) -> String
+    {
+        let mut entries: Vec<(&String, &Stats)> = self.stats.iter().collect();
+        entries.sort_by(|a, b| b.1.total.cmp(&a.1.total).then_with(|| a.0.cmp(b.0)));
+
+        let mut lines = vec![format!("{:>10} {:>12} {:>12}  name", "calls", "total ms", "own ms")];
+        for (name, stats) in entries
+        {
+            lines.push(format!("{:>10} {:>12.3} {:>12.3}  {}", stats.calls, milliseconds(stats.total), milliseconds(stats.own), name));
+        }
+
+        lines.join("\n")
+    }
+
+    // The own time of every stack in the collapsed format flamegraph
+    // tools read: one line per stack, frames outermost first separated
+    // by ';', then a space and the time in nanoseconds.
+    pub fn collapsed_stacks(&self) -> String
+    {
+        let mut stacks: Vec<(String, &Duration)> = self.stacks.iter()
+            .map(|(&stack, own)| (self.stack_path(stack), own))
+            .collect();
+        stacks.sort();
+
+        stacks.iter()
+            .map(|(path, own)| format!("{} {}\n", path, own.as_nanos()))
+            .collect()
+    }
+
+    // The names of a stack's frames, outermost first and separated by
+    // ';', the way collapsed stacks are written.
+    fn stack_path(&self, stack: usize) -> String
+    {
+        let mut names = Vec::new();
+        let mut next = Some(stack);
+        while let Some(stack) = next
+        {
+            let (parent, name) = &self.stack_nodes[stack];
+            names.push(name.as_str());
+            next = *parent;
+        }
+
+        names.reverse();
+        names.join(";")
+    }
+}
+
+fn milliseconds(duration: Duration) -> f64
+{
+    duration.as_secs_f64() * 1000.0
+}
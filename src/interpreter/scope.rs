@@ -3,9 +3,16 @@
 // Author: Quinn Bankhead
 // Project: PostScript Interpreter (CptS 355 - Mini Project)
 // Description:
-// Enumerates the two scope modes that are available.
+// Enumerates the two scope modes that are available, and defines the
+// activation frames lexically scoped procedures run in.
 // -----------------------------------------------------------------------------
 
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+use super::dict::{Dict, EnvRef, PsDict};
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ScopeMode
 {
@@ -14,4 +21,157 @@ pub enum ScopeMode
 
     // For lexical scoping.
     Lexical,
-}
\ No newline at end of file
+}
+
+impl ScopeMode
+{
+    // The name setscoping accepts and currentscoping pushes for the mode.
+    pub fn name(self) -> &'static str
+    {
+        match self
+        {
+            ScopeMode::Dynamic => "dynamic",
+            ScopeMode::Lexical => "lexical",
+        }
+    }
+
+    // Parses a mode name, the reverse of name.
+    pub fn from_name(name: &str) -> Option<ScopeMode>
+    {
+        match name
+        {
+            "dynamic" => Some(ScopeMode::Dynamic),
+            "lexical" => Some(ScopeMode::Lexical),
+            _ => None,
+        }
+    }
+}
+
+// How lexical procedures see and make definitions
+// -----------------------------------------------
+//
+// Capture. A procedure literal captures an environment when it is pushed.
+// At the top level that is a snapshot of the dictionary stack, so later
+// top level definitions do not change what the procedure sees. Inside a
+// running lexical procedure it is that call's activation itself (shared,
+// not copied), so a closure sees the locals of the call that made it.
+// A procedure scanned out of a string by token captures in the same way
+// when token returns it. def stores procedures as they are and never
+// captures, so a procedure made dynamic on purpose stays dynamic.
+//
+// Activations. Calling a lexical procedure by name starts an activation:
+// the dictionaries it captured plus a fresh local dictionary on top.
+// While the body runs, def writes into the top of the activation, begin
+// pushes onto it and end pops from it, but end can never remove the local
+// dictionary or anything below it. The live dictionary stack is left
+// alone, so { /x 1 def x } always sees its own x, and the x is gone once
+// the call returns.
+//
+// Control operators. A body run by if, ifelse, repeat or for does
+// not start an activation. It runs in the activation it was captured in,
+// so its definitions land in the enclosing call. A body captured at the
+// top level writes into the live dictionary stack, as it would without
+// the procedure around it.
+//
+// Lookup. Names are looked up in the captured environment (or activation)
+// first, from the top down. Names it does not bind are then looked up in
+// the live dictionary stack, which is what lets a procedure call itself
+// or call procedures that were defined after it.
+//
+// Ownership. A procedure captured inside a call holds the call's
+// activation, and a def in the call stores it in the activation's local
+// dictionary, so the two would own each other and never be freed. A
+// procedure stored in the local dictionary of its own activation holds
+// it weakly instead, and gets its strong reference back when it is looked
+// up or fetched with get. Closures that leave the call on the operand
+// stack, or are stored anywhere else, keep the activation alive.
+//
+// Dynamically scoped procedures capture nothing and always use the live
+// dictionary stack for both lookups and definitions.
+//
+// Switching modes. setscoping only changes the mode of code run from the
+// top level from then on. A procedure keeps the mode it was made in (see
+// Procedure::scope_mode) and its body, including procedure literals in
+// it, always runs in that mode. The lexical and dynamic operators change
+// the mode of a single procedure, so both kinds can be mixed freely.
+
+// The frames a running lexical procedure call defines names in, and how
+// many of them (the captured frames plus the local dictionary) end is not
+// allowed to remove.
+#[derive(Clone)]
+pub struct Activation
+{
+    pub frames: EnvRef,
+    pub base: usize,
+}
+
+impl Activation
+{
+    // Starts a call: a copy of the captured frame list (the dictionaries
+    // themselves are shared) with a new local dictionary on top.
+    pub fn enter(captured: &EnvRef) -> Self
+    {
+        let mut frames = captured.borrow().clone();
+        frames.push(Rc::new(RefCell::new(PsDict::new())));
+
+        Self
+        {
+            base: frames.len(),
+            frames: Rc::new(RefCell::new(frames)),
+        }
+    }
+
+    // The dictionary def writes into.
+    pub fn current(&self) -> Dict
+    {
+        Rc::clone(self.frames.borrow().last().unwrap())
+    }
+
+    // The local dictionary the call started with.
+    pub fn local(&self) -> Dict
+    {
+        Rc::clone(&self.frames.borrow()[self.base - 1])
+    }
+
+    // The same activation without keeping it alive.
+    pub fn downgrade(&self) -> WeakActivation
+    {
+        WeakActivation
+        {
+            frames: Rc::downgrade(&self.frames),
+            base: self.base,
+        }
+    }
+}
+
+// An activation held by a procedure stored in the activation's own local
+// dictionary, see Procedure::stored_in.
+#[derive(Clone)]
+pub struct WeakActivation
+{
+    frames: Weak<RefCell<Vec<Dict>>>,
+    base: usize,
+}
+
+impl WeakActivation
+{
+    // The activation, or None once nothing else keeps it alive.
+    pub fn upgrade(&self) -> Option<Activation>
+    {
+        Some(Activation
+        {
+            frames: self.frames.upgrade()?,
+            base: self.base,
+        })
+    }
+}
+
+// Activations usually contain the procedures that refer back to them, so
+// only the depth is shown.
+impl fmt::Debug for Activation
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "Activation({} frames)", self.frames.borrow().len())
+    }
+}
@@ -6,6 +6,7 @@
 // Defines the OperandStack type and implements different operations it can handle.
 // -----------------------------------------------------------------------------
 
+use super::error::PsError;
 use super::value::Value;
 
 #[derive(Debug)]
@@ -14,6 +15,27 @@ use super::value::Value;
 pub struct OperandStack
 {
     items: Vec<Value>,
+
+    // Copies of the values popped from below low, oldest pop first, so
+    // a failing operator can put back what it took (see watch).
+    popped: Vec<Value>,
+
+    // The lowest depth the stack has had since the innermost watch
+    // started, 0 when nothing is watching.
+    low: usize,
+}
+
+// What watch hands back, needed to end the watch with release or restore.
+pub struct Watch
+{
+    // The depth when the watch started.
+    start: usize,
+
+    // The low mark of the enclosing watch.
+    low: usize,
+
+    // The length of the popped list when the watch started.
+    popped: usize,
 }
 
 
@@ -25,7 +47,9 @@ impl OperandStack
     {
         Self
         { 
-            items: Vec::new()
+            items: Vec::new(),
+            popped: Vec::new(),
+            low: 0,
         }
     }
 
@@ -36,10 +60,21 @@ impl OperandStack
     }
 
     // Pop method for popping something off the OperandStack
-    // Will return an error if the stack is empty and this is invoked.
-    pub fn pop(&mut self) -> Result<Value, String>
+    // Will return an error if the stack is empty and this is invoked. The
+    // interpreter adds the name of the operator that was popping.
+    pub fn pop(&mut self) -> Result<Value, PsError>
     {
-        self.items.pop().ok_or_else(|| "Operand stack underflow".to_string())
+        let value = self.items.pop().ok_or_else(|| PsError::new("stackunderflow"))?;
+
+        // Values from below the low mark were there before the watch
+        // started, keep a copy in case they have to be put back.
+        if self.items.len() < self.low
+        {
+            self.low = self.items.len();
+            self.popped.push(value.clone());
+        }
+
+        Ok(value)
     }
 
     // Peek method for getting the value at the top of the OperandStack
@@ -54,10 +89,64 @@ impl OperandStack
         self.items.len()
     }
 
+    // Is_empty method for checking if the OperandStack has no items.
+    pub fn is_empty(&self) -> bool
+    {
+        self.items.is_empty()
+    }
+
     // Clear method for clearing all the items in the OperandStack.
     pub fn clear(&mut self)
     {
+        let watched = self.items.len().min(self.low);
+        self.popped.extend(self.items.drain(..watched).rev());
         self.items.clear();
+        self.low = 0;
+    }
+
+    // Starts keeping what is popped from the stack from now on, so an
+    // operator that fails can leave its operands as they were. Watches
+    // nest, every one must end with release or restore.
+    pub fn watch(&mut self) -> Watch
+    {
+        let watch = Watch
+        {
+            start: self.items.len(),
+            low: self.low,
+            popped: self.popped.len(),
+        };
+
+        self.low = self.items.len();
+        watch
+    }
+
+    // Ends a watch, keeping the changes made since it started. Copies
+    // the enclosing watch needs, of values that were there before it
+    // started, are kept for it.
+    pub fn release(&mut self, watch: Watch)
+    {
+        // The values popped during the watch were at depths start - 1
+        // down to low, the first ones are above the enclosing low mark.
+        let newer = watch.start.saturating_sub(watch.low);
+        let end = (watch.popped + newer).min(self.popped.len());
+        self.popped.drain(watch.popped..end);
+
+        self.low = self.low.min(watch.low);
+    }
+
+    // Ends a watch, putting the stack back the way it was when it started.
+    pub fn restore(&mut self, watch: Watch)
+    {
+        self.items.truncate(self.low);
+        self.items.extend(self.popped.drain(watch.popped..).rev());
+        self.low = watch.low;
+    }
+
+    // The top count items of the OperandStack (fewer if it holds fewer),
+    // bottom first, without copying them.
+    pub fn top(&self, count: usize) -> &[Value]
+    {
+        &self.items[self.items.len().saturating_sub(count)..]
     }
 
     // Snapshot method that gets the items in the OperandStack
@@ -65,4 +154,12 @@ impl OperandStack
     {
         self.items.clone()
     }
-}
\ No newline at end of file
+}
+
+impl Default for OperandStack
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
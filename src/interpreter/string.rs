@@ -0,0 +1,289 @@
+// -----------------------------------------------------------------------------
+// File: string.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Defines the PsString type, a shared and mutable byte string that behaves
+// like a PostScript string object.
+// -----------------------------------------------------------------------------
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use super::error::PsError;
+use super::value::Access;
+use super::vm::next_serial;
+
+// The bytes behind one or more string objects, together with the
+// allocation serial number save and restore use to tell old strings
+// from new ones, and the VM the bytes were allocated in.
+struct StringBuffer
+{
+    bytes: RefCell<Vec<u8>>,
+    serial: u64,
+
+    // True if the buffer lives in global VM, outside save and restore.
+    global: bool,
+
+    // Serial of the last save that journaled these bytes, 0 if none has.
+    saved_at: Cell<u64>,
+}
+
+// In PostScript a string object is a reference to a run of bytes.
+// Copying the object (dup, def, getinterval, ...) does not copy the
+// bytes, so a change made through one reference is seen by all of them.
+// PsString models that by keeping the bytes in a shared buffer and
+// describing which part of that buffer this object looks at.
+#[derive(Clone)]
+pub struct PsString
+{
+    // The buffer shared by every view of the same string.
+    storage: Rc<StringBuffer>,
+
+    // Offset of the first byte of this view inside the buffer.
+    start: usize,
+
+    // Number of bytes this view covers.
+    len: usize,
+
+    // Access attribute of this string object. It belongs to the object,
+    // not the buffer, so other views of the bytes keep their own access.
+    access: Access,
+}
+
+impl PsString
+{
+    // Creates a brand new string in local VM that owns the given bytes.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self
+    {
+        Self::allocate(bytes, false)
+    }
+
+    // Creates a brand new string that owns the given bytes, in global VM
+    // if global is true and in local VM otherwise.
+    pub fn allocate(bytes: Vec<u8>, global: bool) -> Self
+    {
+        let len = bytes.len();
+
+        Self
+        {
+            storage: Rc::new(StringBuffer
+            {
+                bytes: RefCell::new(bytes),
+                serial: next_serial(),
+                global,
+                saved_at: Cell::new(0),
+            }),
+            start: 0,
+            len,
+            access: Access::Unlimited,
+        }
+    }
+
+    // Number of bytes in the string.
+    pub fn len(&self) -> usize
+    {
+        self.len
+    }
+
+    // True when the string has no bytes.
+    pub fn is_empty(&self) -> bool
+    {
+        self.len == 0
+    }
+
+    // Reads the byte at the given index, or None if it is out of range.
+    pub fn get(&self, index: usize) -> Option<u8>
+    {
+        if index >= self.len
+        {
+            return None;
+        }
+
+        Some(self.storage.bytes.borrow()[self.start + index])
+    }
+
+    // Overwrites the byte at the given index. Every view sharing the
+    // buffer sees the change.
+    pub fn put(&self, index: usize, byte: u8) -> Result<(), PsError>
+    {
+        if index >= self.len
+        {
+            return Err(PsError::new("rangecheck"));
+        }
+
+        self.storage.bytes.borrow_mut()[self.start + index] = byte;
+        Ok(())
+    }
+
+    // Returns a view of count bytes starting at index. The result shares
+    // the same buffer, so writing into it changes this string too.
+    pub fn interval(&self, index: usize, count: usize) -> Option<PsString>
+    {
+        if index + count > self.len
+        {
+            return None;
+        }
+
+        Some(Self
+        {
+            storage: Rc::clone(&self.storage),
+            start: self.start + index,
+            len: count,
+            access: self.access,
+        })
+    }
+
+    // Copies the given bytes into this string starting at index.
+    pub fn put_interval(&self, index: usize, source: &[u8]) -> Result<(), PsError>
+    {
+        if index + source.len() > self.len
+        {
+            return Err(PsError::new("rangecheck"));
+        }
+
+        let begin = self.start + index;
+        self.storage.bytes.borrow_mut()[begin..begin + source.len()].copy_from_slice(source);
+        Ok(())
+    }
+
+    // Copies the bytes of this view out into an owned vector.
+    pub fn to_bytes(&self) -> Vec<u8>
+    {
+        self.storage.bytes.borrow()[self.start..self.start + self.len].to_vec()
+    }
+
+    // Converts the bytes to text, replacing anything that is not valid UTF-8.
+    pub fn to_string_lossy(&self) -> String
+    {
+        String::from_utf8_lossy(&self.to_bytes()).into_owned()
+    }
+
+    // The access attribute of this string object.
+    pub fn access(&self) -> Access
+    {
+        self.access
+    }
+
+    // Returns the same string object with a new access attribute.
+    pub fn with_access(&self, access: Access) -> PsString
+    {
+        Self
+        {
+            access,
+            ..self.clone()
+        }
+    }
+
+    // Allocation serial number of the underlying buffer.
+    pub fn serial(&self) -> u64
+    {
+        self.storage.serial
+    }
+
+    // True if the string was allocated in global VM.
+    pub fn is_global(&self) -> bool
+    {
+        self.storage.global
+    }
+
+    // Serial of the last save that journaled the underlying buffer.
+    pub fn saved_at(&self) -> u64
+    {
+        self.storage.saved_at.get()
+    }
+
+    // Marks the underlying buffer as journaled by the given save.
+    pub fn set_saved_at(&self, serial: u64)
+    {
+        self.storage.saved_at.set(serial);
+    }
+
+    // Copies out every byte of the underlying buffer, not just this view.
+    pub fn buffer_bytes(&self) -> Vec<u8>
+    {
+        self.storage.bytes.borrow().clone()
+    }
+
+    // Overwrites every byte of the underlying buffer, used by restore.
+    // Strings never change length so the saved bytes always fit.
+    pub fn restore_buffer(&self, bytes: &[u8])
+    {
+        self.storage.bytes.borrow_mut().copy_from_slice(bytes);
+    }
+
+    // True when both objects are views of the same buffer.
+    pub fn same_buffer(&self, other: &PsString) -> bool
+    {
+        Rc::ptr_eq(&self.storage, &other.storage)
+    }
+
+    // True when both objects look at the same bytes of the same buffer.
+    pub fn same_object(&self, other: &PsString) -> bool
+    {
+        Rc::ptr_eq(&self.storage, &other.storage) && self.start == other.start && self.len == other.len
+    }
+}
+
+impl From<&str> for PsString
+{
+    fn from(text: &str) -> Self
+    {
+        PsString::from_bytes(text.as_bytes().to_vec())
+    }
+}
+
+// Two strings are equal when they hold the same bytes, the same rule
+// the eq operator uses.
+impl PartialEq for PsString
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl PartialEq<str> for PsString
+{
+    fn eq(&self, other: &str) -> bool
+    {
+        self.to_bytes() == other.as_bytes()
+    }
+}
+
+// Shows the string the way it would be written in a PostScript program.
+impl fmt::Debug for PsString
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "({})", escape_bytes(&self.to_bytes()))
+    }
+}
+
+// Escapes bytes so they can be placed between ( and ) and read back by
+// the tokenizer unchanged. Printable ASCII is kept as is, everything
+// else becomes an escape sequence.
+pub fn escape_bytes(bytes: &[u8]) -> String
+{
+    let mut escaped = String::new();
+
+    for &byte in bytes
+    {
+        match byte
+        {
+            b'(' => escaped.push_str("\\("),
+            b')' => escaped.push_str("\\)"),
+            b'\\' => escaped.push_str("\\\\"),
+            b'\n' => escaped.push_str("\\n"),
+            b'\r' => escaped.push_str("\\r"),
+            b'\t' => escaped.push_str("\\t"),
+            0x08 => escaped.push_str("\\b"),
+            0x0c => escaped.push_str("\\f"),
+            0x20..=0x7e => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+
+    escaped
+}
@@ -7,163 +7,483 @@
 // given input string.
 // -----------------------------------------------------------------------------
 
-use super::value::Value;
+use std::fmt;
+use std::rc::Rc;
+use std::slice::Iter;
+
+use super::string::PsString;
+use super::value::{Procedure, Value};
 
 
 #[derive(Clone, Debug)]
 
 // Tokens are either literals which are handled in the
 // Value enum, or function names which need to be executed.
+// Both remember where in the source they were read.
 pub enum Token
 {
     // Any valid Value enum.
-    Literal(Value),
+    Literal(Value, Position),
 
     // Any function name to be executed.
-    ExecName(String),
+    ExecName(String, Position),
+}
+
+impl Token
+{
+    // Where the token was read.
+    pub fn position(&self) -> &Position
+    {
+        match self
+        {
+            Token::Literal(_, position) | Token::ExecName(_, position) => position,
+        }
+    }
+}
+
+// Where a token starts: the name of the source it came from (a file
+// path, -c or similar) and the line and column, both counted from 1.
+// Columns count bytes, so a multibyte UTF-8 character takes several.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position
+{
+    pub source: Rc<str>,
+    pub line: u32,
+    pub column: u32,
+}
+
+// Written the way compilers write positions, for example script.ps:3:7.
+impl fmt::Display for Position
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(f, "{}:{}:{}", self.source, self.line, self.column)
+    }
+}
+
+// The bytes of a source with one byte of lookahead, keeping count of the
+// line and column of the next byte. The tokenizer works on bytes rather
+// than text so string literals keep whatever bytes the source holds,
+// valid UTF-8 or not.
+pub struct SourceBytes<'a>
+{
+    bytes: Iter<'a, u8>,
+    peeked: Option<Option<u8>>,
+    source: Rc<str>,
+    line: u32,
+    column: u32,
+}
+
+impl<'a> SourceBytes<'a>
+{
+    pub fn new(src: &'a [u8], source: &str) -> Self
+    {
+        Self
+        {
+            bytes: src.iter(),
+            peeked: None,
+            source: Rc::from(source),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    // The next byte, without consuming it.
+    pub fn peek(&mut self) -> Option<&u8>
+    {
+        let bytes = &mut self.bytes;
+        self.peeked.get_or_insert_with(|| bytes.next().copied()).as_ref()
+    }
+
+    // The position of the next byte.
+    pub fn next_position(&self) -> Position
+    {
+        Position
+        {
+            source: Rc::clone(&self.source),
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+impl Iterator for SourceBytes<'_>
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8>
+    {
+        let next = match self.peeked.take()
+        {
+            Some(peeked) => peeked,
+            None => self.bytes.next().copied(),
+        };
+
+        match next
+        {
+            Some(b'\n') =>
+            {
+                self.line += 1;
+                self.column = 1;
+            }
+            Some(_) => self.column += 1,
+            None => {}
+        }
+
+        next
+    }
 }
 
 // Method will assign a token to its respective Value.
-pub fn parse_atomic_token(raw: &str) -> Result<Token, String>
+pub fn parse_atomic_token(raw: &str, position: Position) -> Result<Token, String>
 {
     if let Ok(i) = raw.parse::<i32>()
     {
         // The token is an integer type.
-        return Ok(Token::Literal(Value::Int(i)));
+        return Ok(Token::Literal(Value::Int(i), position));
     }
 
     if let Ok(f ) = raw.parse::<f64>()
     {
-        return Ok(Token::Literal(Value::Real(f)));
+        return Ok(Token::Literal(Value::Real(f), position));
     }
     if raw == "true"
     {
         // The token is a boolean type with the value true.
-        return Ok(Token::Literal(Value::Bool(true)));
+        return Ok(Token::Literal(Value::Bool(true), position));
     }
 
     if raw == "false"
     {
         // The token is a boolean type with the value false.
-        return Ok(Token::Literal(Value::Bool(false)));
+        return Ok(Token::Literal(Value::Bool(false), position));
     }
 
-    if raw.starts_with('/')
+    if let Some(name) = raw.strip_prefix('/')
     {
         // The token is a variable type.
-        return Ok(Token::Literal(Value::Name(raw[1..].to_string())));
+        return Ok(Token::Literal(Value::Name(name.to_string()), position));
     }
     
     // The token is not a Value, it must be a defined variable or a function name.
-    Ok(Token::ExecName(raw.to_string()))
+    Ok(Token::ExecName(raw.to_string(), position))
     
 }
 
 // Method will tokenize a string and return 
 pub fn tokenize(src: &str) -> Result<Vec<Token>, String>
+{
+    tokenize_in(src.as_bytes(), false, "-")
+}
+
+// Same as tokenize, but string literals are allocated in global VM
+// when global is true, and positions name the given source.
+pub fn tokenize_in(src: &[u8], global: bool, source: &str) -> Result<Vec<Token>, String>
 {
     // Create a vector that will hold the parsed tokens.
     let mut tokens = Vec::new();
 
-    // Convert to character iterator so we can parse { }, ( )
-    let mut chars = src.chars().peekable();
+    // Convert to byte iterator so we can parse { }, ( )
+    let mut chars = SourceBytes::new(src, source);
+
+    // Main tokenizer loop, reads one token at a time until the input runs out.
+    while let Some(token) = next_token(&mut chars, global)?
+    {
+        tokens.push(token);
+    }
+
+    // Return the vector Result.
+    Ok(tokens)
+}
+
+// Scans a single token from the front of src, the way the token operator
+// does. Returns the token and the number of bytes consumed, which includes
+// one whitespace character after the token if there is one. Returns None
+// when src holds nothing but whitespace. String literals are allocated
+// in global VM when global is true.
+pub fn scan_token(src: &[u8], global: bool) -> Result<Option<(Token, usize)>, String>
+{
+    let mut chars = SourceBytes::new(src, "string");
 
-    // Main tokenizer loop (replaces split_whitespace only)
-    while let Some(&atomic_character) = chars.peek() {
+    let token = match next_token(&mut chars, global)?
+    {
+        Some(token) => token,
+        None => return Ok(None),
+    };
 
-        // Skip whitespace
-        if atomic_character.is_whitespace() {
+    // Consume a single whitespace character that ends the token.
+    if chars.peek().is_some_and(|&c| is_whitespace(c))
+    {
+        chars.next();
+    }
+
+    // Whatever the iterator has not consumed yet is the remainder.
+    let remaining = chars.count();
+    Ok(Some((token, src.len() - remaining)))
+}
+
+// Skips over whitespace and comments in front of the next token. A
+// comment runs from % to the end of the line, which covers the %!PS
+// header at the top of a PostScript file too.
+fn skip_whitespace(chars: &mut SourceBytes)
+{
+    while let Some(&c) = chars.peek()
+    {
+        if c == b'%'
+        {
+            while chars.peek().is_some_and(|&c| c != b'\n' && c != b'\r')
+            {
+                chars.next();
+            }
+        }
+        else if is_whitespace(c)
+        {
             chars.next();
-            continue;
         }
+        else
+        {
+            break;
+        }
+    }
+}
 
+// Reads the next token from the character stream, or None at the end of input.
+fn next_token(chars: &mut SourceBytes, global: bool) -> Result<Option<Token>, String>
+{
+    // Skip whitespace
+    skip_whitespace(chars);
+
+    let atomic_character = match chars.peek()
+    {
+        Some(&c) => c,
+        None => return Ok(None),
+    };
+    let position = chars.next_position();
+
+    match atomic_character
+    {
         // Parse procedure literal { ... }
-        if atomic_character == '{' 
+        b'{' =>
         {
             // Consume '{'
-            chars.next(); 
-
-            // Body of the procedure
-            let mut body = String::new();
+            chars.next();
 
-            // Need to track depth because of nested procedures.
-            let mut depth = 1; 
+            // Body of the procedure, nested procedures and strings
+            // are read by the recursive calls.
+            let mut body = Vec::new();
 
-            while let Some(current_character) = chars.next() 
+            loop
             {
-                match current_character 
+                skip_whitespace(chars);
+
+                if chars.peek() == Some(&b'}')
                 {
-                    '{' => 
-                    { 
-                        depth += 1; body.push(current_character); 
-                    }
+                    // Procedure literal finished
+                    chars.next();
+                    break;
+                }
 
-                    '}' => 
-                    {
-                        depth -= 1;
-                        if depth == 0 
-                        {
-                            // Procedure literal finished
-                            break; 
-                        }
-                        else
-                        {
-                            body.push(current_character);
-                        }
-                    }
-                    _ => body.push(current_character)
+                match next_token(chars, global)?
+                {
+                    Some(token) => body.push(token),
+                    None => return Err("Unmatched '{' in procedure literal".into()),
                 }
             }
 
-            if depth != 0 
+            Ok(Some(Token::Literal(Value::Procedure(Procedure::new(body, None)), position)))
+        }
+
+        // String literal ( ... )
+        b'(' =>
+        {
+            // consume '('
+            chars.next();
+            let bytes = parse_string_literal(chars)?;
+
+            Ok(Some(Token::Literal(Value::Str(PsString::allocate(bytes, global)), position)))
+        }
+
+        // Hex string literal < ... >, or << which starts a dictionary.
+        b'<' =>
+        {
+            // consume '<'
+            chars.next();
+            if chars.peek() == Some(&b'<')
             {
-                return Err("Unmatched '{' in procedure literal".into());
+                chars.next();
+                return Ok(Some(Token::ExecName("<<".to_string(), position)));
             }
 
-            // Recursively tokenize the inside of the procedure
-            let inner = tokenize(&body)?;
-            tokens.push(Token::Literal(Value::Procedure(inner, None)));
-            continue;
+            let bytes = parse_hex_string(chars)?;
+
+            Ok(Some(Token::Literal(Value::Str(PsString::allocate(bytes, global)), position)))
         }
 
-        // String literal ( ... )
-        if atomic_character == '(' 
+        // >> ends a dictionary, a single > is only valid after a hex string.
+        b'>' =>
         {
-            // consume '('
-            chars.next(); 
-            let mut string_to_push = String::new();
+            chars.next();
+            if chars.next() != Some(b'>')
+            {
+                return Err("Unmatched '>' in input".into());
+            }
+
+            Ok(Some(Token::ExecName(">>".to_string(), position)))
+        }
 
-            while let Some(current_character) = chars.next() 
+        // Closing delimiters without an opening one.
+        b'}' => Err("Unmatched '}' in input".into()),
+        b')' => Err("Unmatched ')' in input".into()),
+
+        // Defualt will be to parse an atomic token (continuous non-whitespace characters)
+        _ =>
+        {
+            let mut raw = Vec::new();
+            while let Some(&current_character) = chars.peek()
             {
-                if current_character == ')' 
+                if is_whitespace(current_character) || is_delimiter(current_character)
                 {
                     break;
                 }
-                string_to_push.push(current_character);
+                raw.push(current_character);
+                chars.next();
             }
 
-            tokens.push(Token::Literal(Value::Str(string_to_push)));
-            continue;
+            parse_atomic_token(&String::from_utf8_lossy(&raw), position).map(Some)
         }
+    }
+}
 
-        // Defualt will be to parse an atomic token (continuous non-whitespace characters)
-        let mut raw = String::new();
-        while let Some(&current_character) = chars.peek() 
+// Characters that end an atomic token even without whitespace.
+fn is_delimiter(character: u8) -> bool
+{
+    matches!(character, b'{' | b'}' | b'(' | b')' | b'<' | b'>' | b'%')
+}
+
+// The whitespace characters of PostScript: space, tab, newline, form
+// feed, carriage return and the null byte.
+fn is_whitespace(character: u8) -> bool
+{
+    character.is_ascii_whitespace() || character == 0
+}
+
+// Reads the body of a ( ... ) string literal up to its closing parenthesis
+// and returns the bytes it stands for. Balanced parentheses may appear
+// inside the string, and backslash escapes allow any byte to be written.
+fn parse_string_literal(chars: &mut SourceBytes) -> Result<Vec<u8>, String>
+{
+    let mut bytes = Vec::new();
+
+    // The opening parenthesis has already been consumed.
+    let mut depth = 1;
+
+    while let Some(current_character) = chars.next()
+    {
+        match current_character
         {
-            if current_character.is_whitespace() || current_character == '{' || current_character == '}' || current_character == '(' || current_character == ')' 
+            b'(' =>
             {
-                break;
+                depth += 1;
+                bytes.push(b'(');
             }
-            raw.push(current_character);
-            chars.next();
+
+            b')' =>
+            {
+                depth -= 1;
+                if depth == 0
+                {
+                    return Ok(bytes);
+                }
+                bytes.push(b')');
+            }
+
+            b'\\' =>
+            {
+                let escaped = chars.next().ok_or("Unterminated escape in string literal")?;
+                match escaped
+                {
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    b'b' => bytes.push(0x08),
+                    b'f' => bytes.push(0x0c),
+                    b'\\' | b'(' | b')' => bytes.push(escaped),
+
+                    // A backslash at the end of a line continues the string
+                    // on the next line without adding a newline.
+                    b'\n' => {}
+                    b'\r' =>
+                    {
+                        if chars.peek() == Some(&b'\n')
+                        {
+                            chars.next();
+                        }
+                    }
+
+                    // Up to three octal digits give the byte value directly.
+                    b'0'..=b'7' =>
+                    {
+                        let mut code = u32::from(escaped - b'0');
+                        for _ in 0..2
+                        {
+                            match chars.peek().and_then(|&c| (c as char).to_digit(8))
+                            {
+                                Some(digit) =>
+                                {
+                                    code = code * 8 + digit;
+                                    chars.next();
+                                }
+                                None => break,
+                            }
+                        }
+                        bytes.push((code & 0xff) as u8);
+                    }
+
+                    // Unknown escapes just drop the backslash.
+                    other => bytes.push(other),
+                }
+            }
+
+            other => bytes.push(other),
         }
+    }
 
-        if !raw.is_empty() 
+    Err("Unmatched '(' in string literal".into())
+}
+
+// Reads the body of a < ... > hex string literal. Whitespace is ignored
+// and an odd final digit is treated as if it were followed by 0.
+fn parse_hex_string(chars: &mut SourceBytes) -> Result<Vec<u8>, String>
+{
+    let mut bytes = Vec::new();
+    let mut pending: Option<u32> = None;
+
+    for current_character in chars.by_ref()
+    {
+        if current_character == b'>'
         {
-            tokens.push(parse_atomic_token(&raw)?);
+            if let Some(high) = pending
+            {
+                bytes.push((high << 4) as u8);
+            }
+            return Ok(bytes);
+        }
+
+        if is_whitespace(current_character)
+        {
+            continue;
+        }
+
+        let digit = (current_character as char)
+            .to_digit(16)
+            .ok_or_else(|| format!("Invalid character '{}' in hex string", current_character.escape_ascii()))?;
+
+        match pending.take()
+        {
+            Some(high) => bytes.push(((high << 4) | digit) as u8),
+            None => pending = Some(digit),
         }
     }
 
-    // Return the vector Result.
-    Ok(tokens)
-}
\ No newline at end of file
+    Err("Unmatched '<' in hex string literal".into())
+}
@@ -7,11 +7,14 @@
 // operand stack.
 // -----------------------------------------------------------------------------
 
-use super::dict::EnvRef;
-use std::collections::HashMap;
-use std::cell::RefCell;
+use std::fmt;
 use std::rc::Rc;
 
+use super::dict::{Dict, EnvRef};
+use super::scope::{Activation, ScopeMode, WeakActivation};
+use super::string::PsString;
+use super::tokenizer::Token;
+
 // These are the values that we want to push onto the stack
 // when encountered.
 #[derive(Clone, Debug)]
@@ -26,14 +29,414 @@ pub enum Value
     // Boolean values
     Bool(bool),
 
-    // Strings
-    Str(String),
+    // Strings (shared, mutable byte buffers)
+    Str(PsString),
     
     // Variable names (ex. /x)
     Name(String),
 
-    Dict(Rc<RefCell<HashMap<String, Value>>>),
+    // Executable names, such as the ones token scans from a string
+    // (ex. add). exec runs them the way the name in a program would run.
+    ExecName(String),
+
+    // Dictionaries (shared between every reference)
+    Dict(Dict),
 
     // Procedure blocks (ex. {5 2 add})
-    Procedure(Vec<super::tokenizer::Token>, Option<EnvRef>),
+    Procedure(Procedure),
+
+    // Save objects pushed by save, holding the serial of their save.
+    Save(u64),
+
+    // The mark pushed by mark and <<, which >> collects entries up to.
+    Mark,
+}
+
+// Access attributes of composite objects, from most to least restrictive.
+// Operators that read or write an object check these first and fail
+// with invalidaccess when the object does not allow it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Access
+{
+    // The object can not be read, written or executed.
+    NoAccess,
+
+    // The object can only be executed.
+    ExecuteOnly,
+
+    // The object can be read and executed but not written.
+    ReadOnly,
+
+    // No restrictions, the default for new objects.
+    Unlimited,
+}
+
+impl Access
+{
+    // True if the contents may be read.
+    pub fn can_read(self) -> bool
+    {
+        self >= Access::ReadOnly
+    }
+
+    // True if the contents may be changed.
+    pub fn can_write(self) -> bool
+    {
+        self == Access::Unlimited
+    }
+
+    // True if the object may be executed.
+    pub fn can_execute(self) -> bool
+    {
+        self >= Access::ExecuteOnly
+    }
+}
+
+// A procedure object: its body, the environment captured for lexical
+// scoping (if any) and its access attribute.
+#[derive(Clone)]
+pub struct Procedure
+{
+    // The tokens that run when the procedure is executed. Copies of the
+    // procedure object share them, which is what makes two procedures
+    // the same object for eq.
+    pub body: Rc<[Token]>,
+
+    // The dictionary stack captured when the procedure was defined
+    // in lexical scoping mode.
+    pub env: Option<EnvRef>,
+
+    // The activation the procedure literal was evaluated in, where its
+    // definitions go when a control operator runs it. None when it was
+    // captured at the top level (see scope.rs).
+    pub home: Option<Activation>,
+
+    // Set in place of env and home while the procedure is stored in the
+    // local dictionary of its home activation, see stored_in.
+    pub stored: Option<WeakActivation>,
+
+    // Access attribute of this procedure object.
+    pub access: Access,
+}
+
+impl Procedure
+{
+    // Creates a procedure with unlimited access.
+    pub fn new(body: Vec<Token>, env: Option<EnvRef>) -> Self
+    {
+        Self
+        {
+            body: body.into(),
+            env,
+            home: None,
+            stored: None,
+            access: Access::Unlimited,
+        }
+    }
+
+    // The scoping mode the procedure was made in. Lexical procedures carry
+    // the environment they captured, dynamic ones never capture one.
+    pub fn scope_mode(&self) -> ScopeMode
+    {
+        if self.env.is_some() || self.stored.is_some()
+        {
+            ScopeMode::Lexical
+        }
+        else
+        {
+            ScopeMode::Dynamic
+        }
+    }
+
+    // The procedure as it is kept in dictionary. One stored in the local
+    // dictionary of its home activation holds the activation weakly, so
+    // the two do not own each other (see scope.rs).
+    pub fn stored_in(self, dictionary: &Dict) -> Procedure
+    {
+        match &self.home
+        {
+            Some(home) if Rc::ptr_eq(&home.local(), dictionary) => Procedure
+            {
+                env: None,
+                home: None,
+                stored: Some(home.downgrade()),
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    // The procedure as it is once taken out of a dictionary, holding its
+    // activation strongly again. If the activation is gone, which needs
+    // the local dictionary to have outlived the call some other way, the
+    // procedure keeps only what the live dictionary stack binds.
+    pub fn retrieved(self) -> Procedure
+    {
+        match self.stored.as_ref().and_then(WeakActivation::upgrade)
+        {
+            Some(home) => Procedure
+            {
+                env: Some(Rc::clone(&home.frames)),
+                home: Some(home),
+                stored: None,
+                ..self
+            },
+            None => Procedure { stored: None, ..self },
+        }
+    }
+}
+
+// The captured environment can contain the procedure itself, so it is
+// not printed, only whether there is one.
+impl fmt::Debug for Procedure
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        f.debug_struct("Procedure")
+            .field("body", &self.body)
+            .field("lexical", &(self.scope_mode() == ScopeMode::Lexical))
+            .field("access", &self.access)
+            .finish()
+    }
+}
+
+impl Value
+{
+    // True for strings and dictionaries allocated in local VM, and for
+    // procedures with such a string or dictionary among the literals in
+    // their body. These may not be stored inside objects that live in
+    // global VM.
+    pub fn is_local_composite(&self) -> bool
+    {
+        match self
+        {
+            Value::Str(s) => !s.is_global(),
+            Value::Dict(d) => !d.borrow().is_global(),
+            Value::Procedure(p) => p.body.iter().any(|token| match token
+            {
+                Token::Literal(value, _) => value.is_local_composite(),
+                Token::ExecName(..) => false,
+            }),
+            _ => false,
+        }
+    }
+
+    // The value as it is kept in dictionary, see Procedure::stored_in.
+    pub fn stored_in(self, dictionary: &Dict) -> Value
+    {
+        match self
+        {
+            Value::Procedure(procedure) => Value::Procedure(procedure.stored_in(dictionary)),
+            other => other,
+        }
+    }
+
+    // The value as it is once taken out of a dictionary, see
+    // Procedure::retrieved.
+    pub fn retrieved(self) -> Value
+    {
+        match self
+        {
+            Value::Procedure(procedure) => Value::Procedure(procedure.retrieved()),
+            other => other,
+        }
+    }
+
+    // The access attribute of a composite value, or None for simple
+    // values (numbers, booleans and names) which have no access attribute.
+    pub fn access(&self) -> Option<Access>
+    {
+        match self
+        {
+            Value::Str(s) => Some(s.access()),
+            Value::Dict(d) => Some(d.borrow().access()),
+            Value::Procedure(p) => Some(p.access),
+            _ => None,
+        }
+    }
+
+    // The PostScript type name of the value, as returned by the type operator.
+    pub fn type_name(&self) -> &'static str
+    {
+        match self
+        {
+            Value::Int(_) => "integertype",
+            Value::Real(_) => "realtype",
+            Value::Bool(_) => "booleantype",
+            Value::Str(_) => "stringtype",
+            Value::Name(_) | Value::ExecName(_) => "nametype",
+            Value::Dict(_) => "dicttype",
+
+            // Procedures are executable arrays in PostScript.
+            Value::Procedure(_) => "arraytype",
+            Value::Save(_) => "savetype",
+            Value::Mark => "marktype",
+        }
+    }
+}
+
+// How many procedures and dictionaries deep == prints before it writes
+// the inner ones as --procedure-- and --dict--.
+pub const PRINT_DEPTH_LIMIT: usize = 10;
+
+// The text = prints for a value: strings without their parentheses,
+// names without their slash, composite objects as --type--. Strings
+// that can not be read are --nostringval--.
+pub fn format_text(value: &Value) -> String
+{
+    match value
+    {
+        Value::Str(s) if !s.access().can_read() => "--nostringval--".to_string(),
+        Value::Str(s) => s.to_string_lossy(),
+        Value::Name(n) | Value::ExecName(n) => n.clone(),
+        Value::Dict(_) => "--dict--".to_string(),
+        Value::Procedure(_) => "--procedure--".to_string(),
+        other => format_value(other),
+    }
+}
+
+// The text == prints for a value, written so it reads back as the same
+// value: strings are escaped, procedures are printed as { ... } and
+// dictionaries as << /key value ... >> with their keys sorted. Objects
+// that can not be read are written as --nostringval--, --procedure--
+// or --dict--.
+// Dictionaries that contain themselves and anything nested deeper than
+// PRINT_DEPTH_LIMIT are cut short as --dict-- or --procedure--.
+pub fn format_value(value: &Value) -> String
+{
+    let mut text = String::new();
+    write_value(&mut text, value, 0, &mut Vec::new());
+    text
+}
+
+// Appends the == text of value, which sits depth procedures and
+// dictionaries deep. open holds the dictionaries being printed around it.
+fn write_value(text: &mut String, value: &Value, depth: usize, open: &mut Vec<Dict>)
+{
+    match value
+    {
+        Value::Int(i) => text.push_str(&i.to_string()),
+        Value::Real(f) => text.push_str(&format_real(*f)),
+        Value::Bool(b) => text.push_str(&b.to_string()),
+        Value::Str(s) if !s.access().can_read() => text.push_str("--nostringval--"),
+        Value::Str(s) => text.push_str(&format!("{:?}", s)), // literal string, escaped
+        Value::Name(n) => text.push_str(&format!("/{}", n)),
+        Value::ExecName(n) => text.push_str(n),
+        Value::Save(_) => text.push_str("--save--"),
+        Value::Mark => text.push_str("--mark--"),
+
+        Value::Procedure(procedure) =>
+        {
+            if depth >= PRINT_DEPTH_LIMIT || !procedure.access.can_read()
+            {
+                text.push_str("--procedure--");
+                return;
+            }
+
+            text.push('{');
+            for (index, token) in procedure.body.iter().enumerate()
+            {
+                if index > 0
+                {
+                    text.push(' ');
+                }
+
+                match token
+                {
+                    Token::Literal(value, _) => write_value(text, value, depth + 1, open),
+                    Token::ExecName(name, _) => text.push_str(name),
+                }
+            }
+            text.push('}');
+        }
+
+        Value::Dict(dictionary) =>
+        {
+            let cycle = open.iter().any(|outer| Rc::ptr_eq(outer, dictionary));
+            if cycle || depth >= PRINT_DEPTH_LIMIT || !dictionary.borrow().access().can_read()
+            {
+                text.push_str("--dict--");
+                return;
+            }
+
+            let mut entries: Vec<(String, Value)> = dictionary.borrow()
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+            open.push(Rc::clone(dictionary));
+            text.push_str("<<");
+            for (key, value) in &entries
+            {
+                text.push_str(&format!(" /{} ", key));
+                write_value(text, value, depth + 1, open);
+            }
+            text.push_str(" >>");
+            open.pop();
+        }
+    }
+}
+
+// Formats a real number the way PostScript prints it: six significant
+// digits, trailing zeros dropped, exponent form for very large or small
+// values, and always with a decimal point so it reads back as a real.
+pub fn format_real(f: f64) -> String
+{
+    if f.is_nan()
+    {
+        return "nan".to_string();
+    }
+
+    if f.is_infinite()
+    {
+        return if f > 0.0 { "inf".to_string() } else { "-inf".to_string() };
+    }
+
+    if f == 0.0
+    {
+        return "0.0".to_string();
+    }
+
+    // Round to six significant digits first, the exponent can change
+    // when rounding carries (for example 999999.5 becomes 1e+06).
+    let rounded: f64 = format!("{:.5e}", f).parse().unwrap();
+    let exponent = rounded.abs().log10().floor() as i32;
+
+    if !(-4..6).contains(&exponent)
+    {
+        // Exponent form, mantissa with trailing zeros removed.
+        let text = format!("{:.5e}", f);
+        let (mantissa, power) = text.split_once('e').unwrap();
+        let mut mantissa = trim_fraction(mantissa);
+        if !mantissa.contains('.')
+        {
+            mantissa.push_str(".0");
+        }
+
+        let power: i32 = power.parse().unwrap();
+        let sign = if power < 0 { '-' } else { '+' };
+        return format!("{}e{}{:02}", mantissa, sign, power.abs());
+    }
+
+    // Fixed form with as many decimals as six significant digits allow.
+    let decimals = (5 - exponent).max(0) as usize;
+    let mut text = trim_fraction(&format!("{:.*}", decimals, f));
+    if !text.contains('.')
+    {
+        text.push_str(".0");
+    }
+    text
+}
+
+// Removes trailing zeros after a decimal point, and the point itself if
+// nothing is left after it.
+fn trim_fraction(text: &str) -> String
+{
+    if !text.contains('.')
+    {
+        return text.to_string();
+    }
+
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
 }
@@ -0,0 +1,281 @@
+// -----------------------------------------------------------------------------
+// File: vm.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Implements PostScript virtual memory snapshots for the save and restore
+// operators.
+// -----------------------------------------------------------------------------
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use super::dict::{Dict, DictStack, PsDict};
+use super::error::PsError;
+use super::exec::ps_error;
+use super::stack::OperandStack;
+use super::string::PsString;
+use super::tokenizer::Token;
+use super::value::Value;
+
+thread_local!
+{
+    // Counter handing out allocation serial numbers. Every string buffer
+    // and dictionary gets the next number when it is created, so anything
+    // with a serial above the one recorded by save was made after it.
+    static NEXT_SERIAL: Cell<u64> = const { Cell::new(1) };
+}
+
+// Returns a fresh allocation serial number.
+pub fn next_serial() -> u64
+{
+    NEXT_SERIAL.with(|serial|
+    {
+        let current = serial.get();
+        serial.set(current + 1);
+        current
+    })
+}
+
+// Everything needed to put virtual memory back the way it was when
+// save was called. Nothing is copied when the save is made: the first
+// time a local dictionary or string made before the save is changed
+// afterwards, its old contents are added to the journal of the newest
+// save (copy on write).
+struct Snapshot
+{
+    // Unique to this save, and the value of the save object PostScript
+    // code gets. Objects with a serial above it were created after the
+    // save.
+    serial: u64,
+
+    // The dictionaries that were on the dictionary stack.
+    frames: Vec<Dict>,
+
+    // The allocation mode in effect when save was called.
+    global: bool,
+
+    // The contents of the dictionaries changed since the save, as they
+    // were before the first change.
+    dicts: Vec<(Dict, PsDict)>,
+
+    // The bytes of the string buffers changed since the save, as they
+    // were before the first change, with the save that had recorded
+    // them before this one.
+    strings: Vec<(PsString, Vec<u8>, u64)>,
+}
+
+// The stack of active saves plus the current allocation mode. Objects
+// allocated in global VM are never journaled, so they keep their
+// contents across restore.
+#[derive(Default)]
+pub struct VirtualMemory
+{
+    saves: Vec<Snapshot>,
+
+    // True when new composite objects are allocated in global VM.
+    pub global: bool,
+}
+
+impl VirtualMemory
+{
+    pub fn new() -> Self
+    {
+        Self
+        {
+            saves: Vec::new(),
+            global: false,
+        }
+    }
+
+    // Number of saves that have not been restored yet.
+    pub fn level(&self) -> usize
+    {
+        self.saves.len()
+    }
+
+    // Starts a new save and returns its serial, which is what the save
+    // object holds. Only the dictionary stack and allocation mode are
+    // recorded now, objects are journaled as they change.
+    pub fn save(&mut self, dict: &DictStack) -> u64
+    {
+        let serial = next_serial();
+
+        self.saves.push(Snapshot
+        {
+            serial,
+            frames: dict.frames(),
+            global: self.global,
+            dicts: Vec::new(),
+            strings: Vec::new(),
+        });
+
+        serial
+    }
+
+    // Call before changing a dictionary. Journals its contents if it
+    // is a local dictionary the newest save has not recorded yet.
+    pub fn record_dict(&mut self, dictionary: &Dict)
+    {
+        let snapshot = match self.saves.last_mut()
+        {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+
+        let mut contents = dictionary.borrow_mut();
+        if contents.is_global() || contents.serial() > snapshot.serial || contents.saved_at() >= snapshot.serial
+        {
+            return;
+        }
+
+        // The copy keeps the previous mark, so restoring it also tells
+        // the enclosing save whether it recorded the dictionary.
+        snapshot.dicts.push((Rc::clone(dictionary), contents.clone()));
+        contents.set_saved_at(snapshot.serial);
+    }
+
+    // Call before changing the bytes of a string. Journals the whole
+    // buffer if it is a local string the newest save has not recorded yet.
+    pub fn record_string(&mut self, string: &PsString)
+    {
+        let snapshot = match self.saves.last_mut()
+        {
+            Some(snapshot) => snapshot,
+            None => return,
+        };
+
+        if string.is_global() || string.serial() > snapshot.serial || string.saved_at() >= snapshot.serial
+        {
+            return;
+        }
+
+        snapshot.strings.push((string.clone(), string.buffer_bytes(), string.saved_at()));
+        string.set_saved_at(snapshot.serial);
+    }
+
+    // The local dictionaries and strings changed since the save with the
+    // given serial was made, counting several views of one string once.
+    // Empty if that save is no longer active.
+    pub fn changed_since(&self, serial: u64) -> (Vec<Dict>, Vec<PsString>)
+    {
+        let mut dicts: Vec<Dict> = Vec::new();
+        let mut strings: Vec<PsString> = Vec::new();
+
+        for snapshot in self.saves.iter().filter(|snapshot| snapshot.serial >= serial)
+        {
+            for (dictionary, _) in &snapshot.dicts
+            {
+                if !dicts.iter().any(|seen| Rc::ptr_eq(seen, dictionary))
+                {
+                    dicts.push(Rc::clone(dictionary));
+                }
+            }
+
+            for (string, _, _) in &snapshot.strings
+            {
+                if !strings.iter().any(|seen| seen.same_buffer(string))
+                {
+                    strings.push(string.clone());
+                }
+            }
+        }
+
+        (dicts, strings)
+    }
+
+    // Ends the save with the given serial without undoing anything, as
+    // if it had never been made. What it journaled moves to the save
+    // below it, unless that save had recorded the object already. Does
+    // nothing if the save is no longer active.
+    pub fn discard(&mut self, serial: u64)
+    {
+        let index = match self.saves.iter().position(|snapshot| snapshot.serial == serial)
+        {
+            Some(index) => index,
+            None => return,
+        };
+
+        let snapshot = self.saves.remove(index);
+        let below = match index.checked_sub(1)
+        {
+            Some(below) => &mut self.saves[below],
+            None => return,
+        };
+
+        for (dictionary, contents) in snapshot.dicts
+        {
+            if contents.saved_at() < below.serial
+            {
+                below.dicts.push((dictionary, contents));
+            }
+        }
+
+        for (string, bytes, saved_at) in snapshot.strings
+        {
+            if saved_at < below.serial
+            {
+                below.strings.push((string, bytes, saved_at));
+            }
+        }
+    }
+
+    // Puts virtual memory back to the state it had when the save with
+    // the given serial was made, discarding that save and any made after
+    // it. Fails with invalidrestore if that save is no longer active or
+    // if the operand stack still holds objects created after it.
+    pub fn restore(&mut self, serial: u64, dict: &DictStack, opstack: &OperandStack) -> Result<(), PsError>
+    {
+        let index = self.saves.iter()
+            .position(|snapshot| snapshot.serial == serial)
+            .ok_or_else(|| ps_error("invalidrestore", "restore"))?;
+
+        // Nothing left on the operand stack may refer to discarded objects.
+        if opstack.top(opstack.len()).iter().any(|value| made_after(value, serial))
+        {
+            return Err(ps_error("invalidrestore", "restore"));
+        }
+
+        // Undo the journals newest first, so an object changed under
+        // several saves ends up as the oldest of them recorded it.
+        let discarded: Vec<Snapshot> = self.saves.drain(index..).collect();
+        for snapshot in discarded.iter().rev()
+        {
+            for (dictionary, contents) in &snapshot.dicts
+            {
+                *dictionary.borrow_mut() = contents.clone();
+            }
+
+            for (string, bytes, saved_at) in &snapshot.strings
+            {
+                string.restore_buffer(bytes);
+                string.set_saved_at(*saved_at);
+            }
+        }
+
+        let oldest = &discarded[0];
+        dict.set_frames(oldest.frames.clone());
+        self.global = oldest.global;
+
+        Ok(())
+    }
+}
+
+// True for local strings and dictionaries created after the save with the
+// given serial, for saves made after it, and for procedures with such an
+// object among the literals in their body.
+fn made_after(value: &Value, serial: u64) -> bool
+{
+    match value
+    {
+        Value::Str(s) => !s.is_global() && s.serial() > serial,
+        Value::Dict(d) => !d.borrow().is_global() && d.borrow().serial() > serial,
+        Value::Save(other) => *other >= serial,
+        Value::Procedure(p) => p.body.iter().any(|token| match token
+        {
+            Token::Literal(value, _) => made_after(value, serial),
+            Token::ExecName(..) => false,
+        }),
+        _ => false,
+    }
+}
@@ -7,6 +7,8 @@
 // -----------------------------------------------------------------------------
 
 pub mod interpreter;
+pub mod repl;
 
 pub use interpreter::exec::Interpreter;
 pub use interpreter::scope::ScopeMode;
+pub use interpreter::error::PsError;
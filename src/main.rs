@@ -7,75 +7,199 @@
 // -----------------------------------------------------------------------------
 
 use std::env;
-use std::io::{self, Write};
-use ps_interpreter::{Interpreter, ScopeMode};
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::process::ExitCode;
+use ps_interpreter::{repl, Interpreter, ScopeMode};
+use ps_interpreter::repl::ReplOptions;
+use ps_interpreter::repl::console::TerminalConsole;
+use ps_interpreter::interpreter::debug::StepMode;
+use ps_interpreter::interpreter::profile::Profiler;
+use ps_interpreter::interpreter::log::LogLevel;
 
-fn main()
+// What the command line asked the interpreter to run.
+enum Source
 {
-    let args: Vec<String> = env::args().collect();
+    // PostScript code given with -c.
+    Code(String),
 
-    let scope = if args.contains(&"--lexical".to_string())
+    // A PostScript file given by path.
+    File(String),
+}
+
+const USAGE: &str = "usage: ps_interpreter [--lexical] [--rollback] [--trace] [--log warn|debug|trace] [--trace-exec] [--debug] [--profile] [--flamegraph file] [-c code] [file.ps ...]";
+
+fn main() -> ExitCode
+{
+    let mut scope = ScopeMode::Dynamic;
+    let mut options = ReplOptions::default();
+    let mut log_level = None;
+    let mut exec_trace = false;
+    let mut debug = false;
+    let mut profile = false;
+    let mut flamegraph = None;
+    let mut sources = Vec::new();
+
+    // Options and sources are handled in the order they are given, so
+    // ps_interpreter -c '/x 1 def' script.ps runs the code first.
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next()
     {
-        ScopeMode::Lexical
+        match arg.as_str()
+        {
+            "--lexical" => scope = ScopeMode::Lexical,
+            "--rollback" => options.rollback = true,
+            "--trace" => log_level = Some(LogLevel::Trace),
+            "--trace-exec" => exec_trace = true,
+            "--debug" => debug = true,
+            "--profile" => profile = true,
+            "--flamegraph" => match args.next()
+            {
+                Some(path) =>
+                {
+                    profile = true;
+                    flamegraph = Some(path);
+                }
+                None =>
+                {
+                    eprintln!("--flamegraph needs a file to write\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "--log" => match args.next().as_deref().and_then(LogLevel::from_name)
+            {
+                Some(level) => log_level = Some(level),
+                None =>
+                {
+                    eprintln!("--log needs a level: warn, debug or trace\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "-c" => match args.next()
+            {
+                Some(code) => sources.push(Source::Code(code)),
+                None =>
+                {
+                    eprintln!("-c needs some code to run\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            "-h" | "--help" =>
+            {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            option if option.starts_with('-') && option != "-" =>
+            {
+                eprintln!("unknown option '{}'\n{}", option, USAGE);
+                return ExitCode::from(2);
+            }
+            path => sources.push(Source::File(path.to_string())),
+        }
     }
-    else
-    {
-        ScopeMode::Dynamic
-    };
+
     // Create an interpreter.
     let mut postscript_interpreter = Interpreter::new(scope);
+    postscript_interpreter.host.log.set_level(log_level);
+    postscript_interpreter.host.exec_trace = exec_trace;
+    if debug
+    {
+        postscript_interpreter.host.debugger = Some(TerminalConsole::debugger());
+    }
+    if profile
+    {
+        postscript_interpreter.host.profiler = Some(Profiler::new());
+    }
 
-    // REPL
-    loop 
+    // With nothing to run, read a whole program from stdin when it is
+    // piped in, and start the REPL when a person is typing.
+    if sources.is_empty()
     {
-        println!("Interpreter is currently in {:?} scoping mode", postscript_interpreter.scope_mode);
-        // print prompt like it is in postscript, and flush so it appears immediately.
-        // without the flush Rust doesnt actually print it to the console.
-        if postscript_interpreter.len() > 0
-        {
-            // something is in the stack so show the size
-            print!("PS<{}> ", postscript_interpreter.len());
-        }
-        else
+        if io::stdin().is_terminal()
         {
-            // nothing is in the stack just print prompt
-            print!("PS> ");
+            repl::run(&mut postscript_interpreter, options);
+            return finish_profile(&postscript_interpreter, flamegraph.as_deref(), ExitCode::SUCCESS);
         }
-        io::stdout().flush().unwrap();
 
-        // read the input
-        let mut input: String = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+        sources.push(Source::File("-".to_string()));
+    }
+
+    // Programs run under the debugger stop at their first token, so
+    // breakpoints can be set before going on.
+    if let Some(debugger) = &mut postscript_interpreter.host.debugger
+    {
+        debugger.mode = StepMode::Into;
+    }
 
-        // trim the whitespace
-        let trimmed = input.trim();
+    let status = run_batch(&mut postscript_interpreter, &sources);
+    finish_profile(&postscript_interpreter, flamegraph.as_deref(), status)
+}
 
-        // if user enters quit the REPL will stop
-        // this ignore case just makes it so the user
-        // doesnt have to worry about uppercase or lowercase
-        if trimmed.eq_ignore_ascii_case("quit")
-        {
-            println!("Exiting interpreter...");
-            break;
-        }
+// When profiling, prints the report on stderr and writes the collapsed
+// stacks for flamegraph tools if a file was given. Returns status, or 2
+// when the file can not be written.
+fn finish_profile(postscript_interpreter: &Interpreter, flamegraph: Option<&str>, status: ExitCode) -> ExitCode
+{
+    let profiler = match &postscript_interpreter.host.profiler
+    {
+        Some(profiler) => profiler,
+        None => return status,
+    };
+
+    eprintln!("{}", profiler.report());
 
-        // ignores empty input
-        if trimmed.is_empty()
+    if let Some(path) = flamegraph
+    {
+        if let Err(e) = fs::write(path, profiler.collapsed_stacks())
         {
-            continue;
+            eprintln!("Error: can not write {}: {}", path, e);
+            return ExitCode::from(2);
         }
+    }
+
+    status
+}
 
-        // run the interpreter.
-        match postscript_interpreter.interpret(trimmed)
+// Runs every source in order and stops at the first error, which is
+// reported on stderr with a non-zero exit status.
+fn run_batch(postscript_interpreter: &mut Interpreter, sources: &[Source]) -> ExitCode
+{
+    for source in sources
+    {
+        let (program, name) = match source
         {
-            Ok(()) => 
-            {
-                println!("Final stack: {:?}", postscript_interpreter.opstack_snapshot());
-            }
-            Err(e) => 
+            Source::Code(code) => (code.clone().into_bytes(), "-c"),
+            Source::File(path) => match read_source(path)
             {
-                println!("Error: {}", e);
-            }
+                Ok(program) => (program, path.as_str()),
+                Err(e) =>
+                {
+                    eprintln!("Error: can not read {}: {}", path, e);
+                    return ExitCode::from(2);
+                }
+            },
+        };
+
+        if let Err(e) = postscript_interpreter.interpret_source(&program, name)
+        {
+            eprintln!("{}", postscript_interpreter.error_report(&e));
+            return ExitCode::FAILURE;
         }
     }
-}
\ No newline at end of file
+
+    ExitCode::SUCCESS
+}
+
+// Reads a program from a file, or from stdin when the path is "-". The
+// bytes are kept as they are, a file does not have to be valid UTF-8.
+fn read_source(path: &str) -> io::Result<Vec<u8>>
+{
+    if path == "-"
+    {
+        let mut program = Vec::new();
+        io::stdin().read_to_end(&mut program)?;
+        return Ok(program);
+    }
+
+    fs::read(path)
+}
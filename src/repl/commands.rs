@@ -0,0 +1,225 @@
+// -----------------------------------------------------------------------------
+// File: commands.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Implements the REPL meta-commands, lines starting with ':' that inspect
+// or control the interpreter instead of running PostScript.
+// -----------------------------------------------------------------------------
+
+use std::fs;
+use std::time::Instant;
+
+use crate::interpreter::builtin::OPERATORS;
+use crate::interpreter::debug::{Breakpoint, StepMode};
+use crate::interpreter::exec::Interpreter;
+use crate::interpreter::scope::ScopeMode;
+use crate::interpreter::value::format_value;
+use super::ReplOptions;
+use super::console::TerminalConsole;
+
+// Every command with its arguments and what it does, shown by :help.
+pub const COMMANDS: &[(&str, &str)] =
+&[
+    (":stack", "show the operand stack, top first"),
+    (":dicts", "show the dictionary stack, top first, with the names in each"),
+    (":scope [lexical|dynamic]", "show or change the scoping mode"),
+    (":load file.ps", "run a PostScript file"),
+    (":rollback [on|off]", "show or change whether failed lines are undone"),
+    (":trace [on|off]", "show or change whether every token executed is logged"),
+    (":debug [on|off]", "show or change whether the step debugger is on"),
+    (":break [name|line|file:line]", "list breakpoints or add one, turning the debugger on"),
+    (":delete n", "remove breakpoint n"),
+    (":step code", "run code in the debugger, stopping at its first token"),
+    (":reset", "start over with empty stacks and dictionaries"),
+    (":time code", "run code and report how long it took"),
+    (":help [operator]", "list commands and operators, or describe one"),
+];
+
+// Runs a meta-command line and returns the text to show for it.
+pub fn meta_command(postscript_interpreter: &mut Interpreter, options: &mut ReplOptions, line: &str) -> String
+{
+    let (command, argument) = match line.split_once(char::is_whitespace)
+    {
+        Some((command, argument)) => (command, argument.trim()),
+        None => (line, ""),
+    };
+
+    match command
+    {
+        ":stack" => show_stack(postscript_interpreter),
+        ":dicts" => postscript_interpreter.dict_listing(),
+
+        // :scope shows the scoping mode, :scope lexical or :scope dynamic
+        // switches it without restarting the interpreter
+        ":scope" => match argument
+        {
+            "" => postscript_interpreter.scope_mode.name().to_string(),
+            name => match ScopeMode::from_name(name)
+            {
+                Some(mode) =>
+                {
+                    postscript_interpreter.scope_mode = mode;
+                    format!("scoping mode is now {}", mode.name())
+                }
+                None => format!("Error: unknown scoping mode '{}', expected lexical or dynamic", name),
+            },
+        },
+
+        ":load" if argument.is_empty() => "Error: :load needs a file name".to_string(),
+        ":load" => match fs::read(argument)
+        {
+            Ok(program) => match postscript_interpreter.interpret_source(&program, argument)
+            {
+                Ok(()) => format!("loaded {}", argument),
+                Err(e) => postscript_interpreter.error_report(&e),
+            },
+            Err(e) => format!("Error: can not read {}: {}", argument, e),
+        },
+
+        // With rollback on, a line that fails leaves no trace behind.
+        ":rollback" => match argument
+        {
+            "" => format!("rollback is {}", if options.rollback { "on" } else { "off" }),
+            "on" | "off" =>
+            {
+                options.rollback = argument == "on";
+                format!("rollback is now {}", argument)
+            }
+            _ => format!("Error: expected :rollback on or :rollback off, not '{}'", argument),
+        },
+
+        // The execution trace, the same switch as settrace.
+        ":trace" => match argument
+        {
+            "" => format!("trace is {}", if postscript_interpreter.host.exec_trace { "on" } else { "off" }),
+            "on" | "off" =>
+            {
+                postscript_interpreter.host.exec_trace = argument == "on";
+                format!("trace is now {}", argument)
+            }
+            _ => format!("Error: expected :trace on or :trace off, not '{}'", argument),
+        },
+
+        // The step debugger. While it is on, breakpoints stop the
+        // program and the debugger reads its own commands.
+        ":debug" => match argument
+        {
+            "" => format!("debugger is {}", if postscript_interpreter.host.debugger.is_some() { "on" } else { "off" }),
+            "on" =>
+            {
+                postscript_interpreter.host.debugger.get_or_insert_with(TerminalConsole::debugger);
+                "debugger is now on".to_string()
+            }
+            "off" =>
+            {
+                postscript_interpreter.host.debugger = None;
+                "debugger is now off".to_string()
+            }
+            _ => format!("Error: expected :debug on or :debug off, not '{}'", argument),
+        },
+
+        ":break" =>
+        {
+            let debugger = postscript_interpreter.host.debugger.get_or_insert_with(TerminalConsole::debugger);
+            if argument.is_empty()
+            {
+                return debugger.breakpoint_listing();
+            }
+
+            match Breakpoint::parse(argument)
+            {
+                Some(breakpoint) => debugger.add_breakpoint(breakpoint),
+                None => format!("Error: can not break at '{}'", argument),
+            }
+        }
+
+        ":delete" => match &mut postscript_interpreter.host.debugger
+        {
+            Some(debugger) => debugger.delete_breakpoint(argument),
+            None => "Error: the debugger is off".to_string(),
+        },
+
+        // Runs the code stepping from its first token, then goes back to
+        // stopping only at breakpoints.
+        ":step" if argument.is_empty() => "Error: :step needs some code to run".to_string(),
+        ":step" =>
+        {
+            postscript_interpreter.host.debugger.get_or_insert_with(TerminalConsole::debugger).mode = StepMode::Into;
+            let result = postscript_interpreter.interpret(argument);
+            if let Some(debugger) = &mut postscript_interpreter.host.debugger
+            {
+                debugger.mode = StepMode::Continue;
+            }
+
+            match result
+            {
+                Ok(()) => show_stack(postscript_interpreter),
+                Err(e) => postscript_interpreter.error_report(&e),
+            }
+        }
+
+        // A new interpreter keeps only the settings: the scoping mode,
+        // the output, the log, the execution trace and the debugger.
+        ":reset" =>
+        {
+            postscript_interpreter.reset();
+            "interpreter reset".to_string()
+        }
+
+        ":time" =>
+        {
+            let start = Instant::now();
+            let result = postscript_interpreter.interpret(argument);
+            let elapsed = start.elapsed();
+
+            match result
+            {
+                Ok(()) => format!("time: {:.3} ms", elapsed.as_secs_f64() * 1000.0),
+                Err(e) => format!("{}\ntime: {:.3} ms", postscript_interpreter.error_report(&e), elapsed.as_secs_f64() * 1000.0),
+            }
+        }
+
+        ":help" => help(postscript_interpreter, argument),
+
+        _ => format!("Error: unknown command '{}', try :help", command),
+    }
+}
+
+// One line per operand, top of the stack first.
+fn show_stack(postscript_interpreter: &Interpreter) -> String
+{
+    if postscript_interpreter.is_empty()
+    {
+        return "(empty stack)".to_string();
+    }
+
+    postscript_interpreter.stack_listing()
+}
+
+// Lists everything with no argument, otherwise describes one operator
+// or the value a name is bound to.
+fn help(postscript_interpreter: &Interpreter, name: &str) -> String
+{
+    if name.is_empty()
+    {
+        let mut lines: Vec<String> = COMMANDS.iter()
+            .map(|(usage, description)| format!("{:<26} {}", usage, description))
+            .collect();
+
+        let operators: Vec<&str> = OPERATORS.iter().map(|(operator, _, _)| *operator).collect();
+        lines.push(format!("operators: {}", operators.join(" ")));
+        return lines.join("\n");
+    }
+
+    if let Some((_, usage, description)) = OPERATORS.iter().find(|(operator, _, _)| *operator == name)
+    {
+        return format!("{}\n    {}", usage, description);
+    }
+
+    match postscript_interpreter.dict.lookup_dynamic(name)
+    {
+        Some(value) => format!("{} is defined as {}", name, format_value(&value)),
+        None => format!("{} is not an operator or a defined name", name),
+    }
+}
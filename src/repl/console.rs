@@ -0,0 +1,67 @@
+// -----------------------------------------------------------------------------
+// File: console.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Connects the step debugger to the terminal, using the REPL's line
+// editor to read debugger commands.
+// -----------------------------------------------------------------------------
+
+use crate::interpreter::debug::{DebugConsole, Debugger, DEBUG_COMMANDS};
+use super::editor::{History, Input, LineEditor};
+
+// Reads debugger commands from the terminal. Its history is kept apart
+// from the REPL's and is not saved.
+pub struct TerminalConsole
+{
+    editor: LineEditor,
+}
+
+impl TerminalConsole
+{
+    pub fn new() -> Self
+    {
+        Self
+        {
+            editor: LineEditor::new(History::load(None)),
+        }
+    }
+
+    // A debugger that talks to the terminal.
+    pub fn debugger() -> Debugger
+    {
+        Debugger::new(Box::new(Self::new()))
+    }
+}
+
+impl Default for TerminalConsole
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl DebugConsole for TerminalConsole
+{
+    // Ctrl-C stops the program the way the quit command does.
+    fn read_command(&mut self, prompt: &str) -> Option<String>
+    {
+        let complete = |word: &str| DEBUG_COMMANDS.iter()
+            .map(|(usage, _)| usage.split([',', ' ']).next().unwrap().to_string())
+            .filter(|command| command.starts_with(word))
+            .collect();
+
+        match self.editor.read_line(prompt, complete)
+        {
+            Ok(Input::Line(line)) => Some(line),
+            Ok(Input::Interrupted) => Some("quit".to_string()),
+            Ok(Input::Eof) | Err(_) => None,
+        }
+    }
+
+    fn show(&mut self, text: &str)
+    {
+        println!("{}", text);
+    }
+}
@@ -0,0 +1,417 @@
+// -----------------------------------------------------------------------------
+// File: editor.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// A small line editor for the REPL: cursor movement, history kept in a
+// file between sessions, and tab completion.
+// -----------------------------------------------------------------------------
+
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+
+use super::terminal::{read_key, Key, RawMode};
+
+// How many lines of history are kept in the history file.
+const HISTORY_LIMIT: usize = 1000;
+
+// The text of the line being edited and the cursor position in it,
+// counted in characters.
+#[derive(Default)]
+pub struct LineBuffer
+{
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+// What tab completion did to the line.
+#[derive(Debug, PartialEq)]
+pub enum Completion
+{
+    // Nothing matched the word before the cursor.
+    NoMatch,
+
+    // The word was extended, fully or up to where the matches differ.
+    Extended,
+
+    // Several names match and none could be added, the caller shows them.
+    Choices(Vec<String>),
+}
+
+impl LineBuffer
+{
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    // The text of the line.
+    pub fn text(&self) -> String
+    {
+        self.chars.iter().collect()
+    }
+
+    // The cursor position, in characters from the start of the line.
+    pub fn cursor(&self) -> usize
+    {
+        self.cursor
+    }
+
+    // Replaces the whole line and puts the cursor at its end.
+    pub fn set(&mut self, text: &str)
+    {
+        self.chars = text.chars().collect();
+        self.cursor = self.chars.len();
+    }
+
+    // Inserts a character at the cursor.
+    pub fn insert(&mut self, c: char)
+    {
+        self.chars.insert(self.cursor, c);
+        self.cursor += 1;
+    }
+
+    // Removes the character before the cursor.
+    pub fn backspace(&mut self)
+    {
+        if self.cursor > 0
+        {
+            self.cursor -= 1;
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    // Removes the character under the cursor.
+    pub fn delete(&mut self)
+    {
+        if self.cursor < self.chars.len()
+        {
+            self.chars.remove(self.cursor);
+        }
+    }
+
+    pub fn left(&mut self)
+    {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn right(&mut self)
+    {
+        self.cursor = (self.cursor + 1).min(self.chars.len());
+    }
+
+    pub fn home(&mut self)
+    {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self)
+    {
+        self.cursor = self.chars.len();
+    }
+
+    // Removes everything before the cursor (Ctrl-U).
+    pub fn kill_to_start(&mut self)
+    {
+        self.chars.drain(..self.cursor);
+        self.cursor = 0;
+    }
+
+    // Removes everything from the cursor on (Ctrl-K).
+    pub fn kill_to_end(&mut self)
+    {
+        self.chars.truncate(self.cursor);
+    }
+
+    // The partial name in front of the cursor. Names end at whitespace
+    // and at PostScript delimiters, so the / of a literal name is not
+    // part of the word.
+    pub fn word_before_cursor(&self) -> String
+    {
+        let start = self.chars[..self.cursor]
+            .iter()
+            .rposition(|&c| c.is_whitespace() || "{}()<>[]/".contains(c))
+            .map_or(0, |index| index + 1);
+
+        self.chars[start..self.cursor].iter().collect()
+    }
+
+    // Completes the word before the cursor from the given names. A single
+    // match is finished off with a space after it. Several matches are
+    // extended as far as they agree, and listed when they already differ
+    // at the cursor.
+    pub fn complete(&mut self, names: &[String]) -> Completion
+    {
+        let word = self.word_before_cursor();
+
+        let mut matches: Vec<&String> = names.iter().filter(|name| name.starts_with(&word)).collect();
+        matches.sort();
+        matches.dedup();
+
+        let common = match matches.split_first()
+        {
+            None => return Completion::NoMatch,
+            Some((first, rest)) => rest.iter().fold(first.as_str(), |common, name| common_prefix(common, name)),
+        };
+
+        if common.len() > word.len()
+        {
+            for c in common[word.len()..].chars()
+            {
+                self.insert(c);
+            }
+        }
+        else if matches.len() > 1
+        {
+            return Completion::Choices(matches.into_iter().cloned().collect());
+        }
+
+        if matches.len() == 1
+        {
+            self.insert(' ');
+        }
+
+        Completion::Extended
+    }
+}
+
+// The longest common start of two strings.
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str
+{
+    let length = a.char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map_or(a.len().min(b.len()), |((index, _), _)| index);
+
+    &a[..length]
+}
+
+// Lines entered in earlier sessions and this one, oldest first, saved to
+// a file after every new entry.
+pub struct History
+{
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl History
+{
+    // Loads the history file, a missing or unreadable file just means
+    // starting with no history. With no path nothing is saved.
+    pub fn load(path: Option<PathBuf>) -> Self
+    {
+        let entries = path.as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+
+        Self { entries, path }
+    }
+
+    // The history file used when none is given: .ps_interpreter_history
+    // in the home directory.
+    pub fn default_path() -> Option<PathBuf>
+    {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join(".ps_interpreter_history"))
+    }
+
+    pub fn entries(&self) -> &[String]
+    {
+        &self.entries
+    }
+
+    // Adds a line unless it is blank or repeats the previous entry, then
+    // writes the most recent entries back to the history file.
+    pub fn add(&mut self, line: &str)
+    {
+        if line.trim().is_empty() || self.entries.last().is_some_and(|last| last == line)
+        {
+            return;
+        }
+
+        self.entries.push(line.to_string());
+        if self.entries.len() > HISTORY_LIMIT
+        {
+            self.entries.drain(..self.entries.len() - HISTORY_LIMIT);
+        }
+
+        if let Some(path) = &self.path
+        {
+            let mut text = self.entries.join("\n");
+            text.push('\n');
+            let _ = fs::write(path, text);
+        }
+    }
+}
+
+// What reading a line produced.
+#[derive(Debug, PartialEq)]
+pub enum Input
+{
+    Line(String),
+
+    // Ctrl-C, the line was thrown away.
+    Interrupted,
+
+    // Ctrl-D on an empty line, or stdin was closed.
+    Eof,
+}
+
+// Reads lines from the terminal with editing, history and completion.
+// When raw mode is not available it reads plain lines instead.
+pub struct LineEditor
+{
+    pub history: History,
+}
+
+impl LineEditor
+{
+    pub fn new(history: History) -> Self
+    {
+        Self { history }
+    }
+
+    // Shows the prompt and reads one line. complete is called with the
+    // word before the cursor when Tab is pressed and returns the names
+    // that could finish it.
+    pub fn read_line(&mut self, prompt: &str, complete: impl Fn(&str) -> Vec<String>) -> io::Result<Input>
+    {
+        let raw_mode = if io::stdin().is_terminal() { RawMode::enable().ok() } else { None };
+
+        let input = match raw_mode
+        {
+            Some(_) => self.edit_line(prompt, complete)?,
+            None => read_plain_line(prompt)?,
+        };
+
+        if let Input::Line(line) = &input
+        {
+            self.history.add(line);
+        }
+
+        Ok(input)
+    }
+
+    // The editing loop, run while the terminal is in raw mode.
+    fn edit_line(&mut self, prompt: &str, complete: impl Fn(&str) -> Vec<String>) -> io::Result<Input>
+    {
+        let mut stdin = io::stdin().lock();
+        let mut line = LineBuffer::new();
+
+        // Position while browsing history, entries().len() is the line
+        // being typed, which is kept in draft meanwhile.
+        let mut position = self.history.entries().len();
+        let mut draft = String::new();
+
+        redraw(prompt, &line)?;
+
+        loop
+        {
+            let key = match read_key(&mut stdin)?
+            {
+                Some(key) => key,
+                None => return Ok(Input::Eof),
+            };
+
+            match key
+            {
+                Key::Enter =>
+                {
+                    print!("\r\n");
+                    io::stdout().flush()?;
+                    return Ok(Input::Line(line.text()));
+                }
+
+                Key::Ctrl('c') =>
+                {
+                    print!("^C\r\n");
+                    io::stdout().flush()?;
+                    return Ok(Input::Interrupted);
+                }
+
+                Key::Ctrl('d') if line.text().is_empty() =>
+                {
+                    print!("\r\n");
+                    io::stdout().flush()?;
+                    return Ok(Input::Eof);
+                }
+
+                Key::Char(c) => line.insert(c),
+                Key::Backspace => line.backspace(),
+                Key::Delete | Key::Ctrl('d') => line.delete(),
+                Key::Left | Key::Ctrl('b') => line.left(),
+                Key::Right | Key::Ctrl('f') => line.right(),
+                Key::Home | Key::Ctrl('a') => line.home(),
+                Key::End | Key::Ctrl('e') => line.end(),
+                Key::Ctrl('u') => line.kill_to_start(),
+                Key::Ctrl('k') => line.kill_to_end(),
+
+                Key::Up | Key::Ctrl('p') if position > 0 =>
+                {
+                    if position == self.history.entries().len()
+                    {
+                        draft = line.text();
+                    }
+                    position -= 1;
+                    line.set(&self.history.entries()[position]);
+                }
+
+                Key::Down | Key::Ctrl('n') if position < self.history.entries().len() =>
+                {
+                    position += 1;
+                    match self.history.entries().get(position)
+                    {
+                        Some(entry) => line.set(entry),
+                        None => line.set(&draft),
+                    }
+                }
+
+                Key::Tab =>
+                {
+                    let names = complete(&line.word_before_cursor());
+                    if let Completion::Choices(choices) = line.complete(&names)
+                    {
+                        print!("\r\n{}\r\n", choices.join("  "));
+                    }
+                }
+
+                _ => {}
+            }
+
+            redraw(prompt, &line)?;
+        }
+    }
+}
+
+// Redraws the prompt and line and puts the cursor back where it belongs.
+fn redraw(prompt: &str, line: &LineBuffer) -> io::Result<()>
+{
+    let text = line.text();
+    let after_cursor = text.chars().count() - line.cursor();
+
+    print!("\r{}{}\x1b[K", prompt, text);
+    if after_cursor > 0
+    {
+        print!("\x1b[{}D", after_cursor);
+    }
+
+    io::stdout().flush()
+}
+
+// Reads a line without any editing, for when stdin is not a terminal.
+fn read_plain_line(prompt: &str) -> io::Result<Input>
+{
+    print!("{}", prompt);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input)? == 0
+    {
+        println!();
+        return Ok(Input::Eof);
+    }
+
+    Ok(Input::Line(input.trim_end_matches(['\n', '\r']).to_string()))
+}
@@ -0,0 +1,87 @@
+// -----------------------------------------------------------------------------
+// File: input.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Decides whether the text typed into the REPL so far is a complete
+// piece of PostScript or is still waiting for closing delimiters.
+// -----------------------------------------------------------------------------
+
+// Returns true while src has an open procedure { , string ( or < , or
+// dictionary << that has not been closed yet, meaning the REPL should
+// keep reading lines before running it. Stray closing delimiters count
+// as complete so the interpreter gets to report them. Delimiters in %
+// comments do not count, the same as for the tokenizer.
+pub fn needs_more_input(src: &str) -> bool
+{
+    let mut chars = src.chars().peekable();
+
+    // How many { and << are open.
+    let mut braces: i32 = 0;
+    let mut dicts: i32 = 0;
+
+    while let Some(current_character) = chars.next()
+    {
+        match current_character
+        {
+            // A comment runs to the end of the line.
+            '%' =>
+            {
+                while chars.next_if(|&c| c != '\n' && c != '\r').is_some() {}
+            }
+
+            '{' => braces += 1,
+            '}' => braces -= 1,
+
+            // String literal, parentheses nest and \ escapes the next character.
+            '(' =>
+            {
+                let mut depth = 1;
+                while depth > 0
+                {
+                    match chars.next()
+                    {
+                        Some('(') => depth += 1,
+                        Some(')') => depth -= 1,
+                        Some('\\') =>
+                        {
+                            chars.next();
+                        }
+                        Some(_) => {}
+                        None => return true,
+                    }
+                }
+            }
+
+            // << opens a dictionary, a single < opens a hex string.
+            '<' if chars.peek() == Some(&'<') =>
+            {
+                chars.next();
+                dicts += 1;
+            }
+            '<' =>
+            {
+                let closed = chars.by_ref().any(|c| c == '>');
+                if !closed
+                {
+                    return true;
+                }
+            }
+            '>' if chars.peek() == Some(&'>') =>
+            {
+                chars.next();
+                dicts -= 1;
+            }
+
+            _ => {}
+        }
+
+        // Too many closers: the input is broken, not unfinished.
+        if braces < 0 || dicts < 0
+        {
+            return false;
+        }
+    }
+
+    braces > 0 || dicts > 0
+}
@@ -0,0 +1,168 @@
+// -----------------------------------------------------------------------------
+// File: mod.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// The interactive read-eval-print loop of the interpreter.
+// -----------------------------------------------------------------------------
+
+pub mod commands;
+pub mod console;
+pub mod editor;
+pub mod input;
+pub mod rollback;
+pub mod terminal;
+
+use crate::interpreter::builtin::OPERATORS;
+use crate::interpreter::exec::Interpreter;
+use commands::{meta_command, COMMANDS};
+use editor::{History, Input, LineEditor};
+use input::needs_more_input;
+use rollback::Checkpoint;
+
+// Settings of a REPL session, changed with command line flags or
+// meta-commands.
+#[derive(Default)]
+pub struct ReplOptions
+{
+    // Undo everything a line did to the operand and dictionary stacks
+    // when the line fails.
+    pub rollback: bool,
+}
+
+// Prompt shown while a procedure, string or dictionary is still open.
+const CONTINUATION_PROMPT: &str = "... ";
+
+// Reads and runs input until the user quits or input ends. Lines are
+// collected until every delimiter is closed, so procedures and strings
+// can be typed over several lines.
+pub fn run(postscript_interpreter: &mut Interpreter, mut options: ReplOptions)
+{
+    // Text typed so far that is not complete yet.
+    let mut pending = String::new();
+
+    let mut editor = LineEditor::new(History::load(History::default_path()));
+
+    loop
+    {
+        // print prompt like it is in postscript
+        let prompt = if !pending.is_empty()
+        {
+            // still inside a procedure, string or dictionary
+            CONTINUATION_PROMPT.to_string()
+        }
+        else if !postscript_interpreter.is_empty()
+        {
+            // something is in the stack so show the size
+            format!("PS<{}> ", postscript_interpreter.len())
+        }
+        else
+        {
+            // nothing is in the stack just print prompt
+            "PS> ".to_string()
+        };
+
+        // read the input, stopping at end of input (Ctrl-D); Ctrl-C
+        // throws away the line and anything still pending
+        let completer = |word: &str| completions(postscript_interpreter, word);
+        let input = match editor.read_line(&prompt, completer)
+        {
+            Ok(Input::Line(line)) => line + "\n",
+            Ok(Input::Interrupted) =>
+            {
+                pending.clear();
+                continue;
+            }
+            Ok(Input::Eof) | Err(_) => break,
+        };
+
+        // Commands are only recognised at the start of a new input.
+        if pending.is_empty()
+        {
+            // trim the whitespace
+            let trimmed = input.trim();
+
+            // if user enters quit the REPL will stop
+            // this ignore case just makes it so the user
+            // doesnt have to worry about uppercase or lowercase
+            if trimmed.eq_ignore_ascii_case("quit")
+            {
+                println!("Exiting interpreter...");
+                break;
+            }
+
+            // ignores empty input
+            if trimmed.is_empty()
+            {
+                continue;
+            }
+
+            if trimmed.starts_with(':')
+            {
+                println!("{}", meta_command(postscript_interpreter, &mut options, trimmed));
+                continue;
+            }
+        }
+
+        pending.push_str(&input);
+        if needs_more_input(&pending)
+        {
+            continue;
+        }
+
+        // run the interpreter.
+        let program = std::mem::take(&mut pending);
+        let checkpoint = options.rollback.then(|| Checkpoint::take(postscript_interpreter));
+        match postscript_interpreter.interpret(&program)
+        {
+            // show the stack the way pstack does, top first
+            Ok(()) =>
+            {
+                if let Some(checkpoint) = checkpoint
+                {
+                    checkpoint.discard(postscript_interpreter);
+                }
+
+                if !postscript_interpreter.is_empty()
+                {
+                    println!("{}", postscript_interpreter.stack_listing());
+                }
+            }
+            Err(e) =>
+            {
+                println!("{}", postscript_interpreter.error_report(&e));
+
+                if let Some(checkpoint) = checkpoint
+                {
+                    println!("{}", checkpoint.restore(postscript_interpreter));
+                }
+            }
+        }
+    }
+}
+
+// Names that could finish the word being typed: REPL commands for words
+// starting with ':', otherwise builtin operators and every name defined
+// on the dictionary stack.
+pub fn completions(postscript_interpreter: &Interpreter, word: &str) -> Vec<String>
+{
+    if word.starts_with(':')
+    {
+        return COMMANDS.iter()
+            .map(|(usage, _)| usage.split_whitespace().next().unwrap().to_string())
+            .filter(|command| command.starts_with(word))
+            .collect();
+    }
+
+    let mut names: Vec<String> = OPERATORS.iter()
+        .map(|(name, _, _)| name.to_string())
+        .filter(|name| name.starts_with(word))
+        .collect();
+
+    for frame in postscript_interpreter.dict.frames()
+    {
+        names.extend(frame.borrow().iter().map(|(name, _)| name.clone()).filter(|name| name.starts_with(word)));
+    }
+
+    names
+}
@@ -0,0 +1,148 @@
+// -----------------------------------------------------------------------------
+// File: rollback.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Records the state of the interpreter before a REPL line runs, so a line
+// that fails can be undone as if it had never been typed.
+// -----------------------------------------------------------------------------
+
+use std::rc::Rc;
+
+use crate::interpreter::dict::{Dict, PsDict};
+use crate::interpreter::exec::Interpreter;
+use crate::interpreter::value::Value;
+
+// The operand stack at one moment plus a save of virtual memory, which
+// covers everything else a line can change: definitions, entries put
+// into any dictionary, string contents, the dictionary stack, saves the
+// line made and the allocation mode. The save journals objects as they
+// change, so taking a checkpoint costs O(stack depth).
+pub struct Checkpoint
+{
+    operands: Vec<Value>,
+
+    // The dictionaries that were on the dictionary stack.
+    frames: Vec<Dict>,
+
+    // The serial of the save taken for the checkpoint.
+    save: u64,
+}
+
+impl Checkpoint
+{
+    pub fn take(postscript_interpreter: &mut Interpreter) -> Self
+    {
+        Self
+        {
+            operands: postscript_interpreter.opstack_snapshot(),
+            frames: postscript_interpreter.dict.frames(),
+            save: postscript_interpreter.vm.save(&postscript_interpreter.dict),
+        }
+    }
+
+    // Keeps whatever the line did, for a line that succeeded.
+    pub fn discard(self, postscript_interpreter: &mut Interpreter)
+    {
+        postscript_interpreter.vm.discard(self.save);
+    }
+
+    // Puts the interpreter back the way it was and describes what that
+    // undid, for example "operand stack back to 1 entries (was 3);
+    // undid definitions of x".
+    pub fn restore(self, postscript_interpreter: &mut Interpreter) -> String
+    {
+        let mut undone = Vec::new();
+
+        // The operand stack.
+        let operands = postscript_interpreter.opstack_snapshot();
+        if operands.len() != self.operands.len() || operands.iter().zip(&self.operands).any(|(a, b)| !same_value(a, b))
+        {
+            undone.push(format!("operand stack back to {} entries (was {})", self.operands.len(), operands.len()));
+        }
+
+        postscript_interpreter.clear();
+        for value in self.operands
+        {
+            postscript_interpreter.push(value);
+        }
+
+        // Dictionaries begun or ended by the line.
+        let depth = postscript_interpreter.dict.frames().len();
+        if depth != self.frames.len()
+        {
+            undone.push(format!("dictionary stack back to {} dictionaries (was {})", self.frames.len(), depth));
+        }
+
+        // The dictionaries as the line left them, to tell afterwards
+        // which definitions it changed.
+        let changed: Vec<PsDict> = self.frames.iter().map(|frame| frame.borrow().clone()).collect();
+
+        // Dictionaries off the dictionary stack and strings the line
+        // changed, through put, putinterval and the like.
+        let (dicts, strings) = postscript_interpreter.vm.changed_since(self.save);
+        let others = dicts.iter().filter(|dictionary| !self.frames.iter().any(|frame| Rc::ptr_eq(frame, dictionary))).count()
+            + strings.len();
+
+        let vm = &mut postscript_interpreter.vm;
+        if vm.restore(self.save, &postscript_interpreter.dict, &postscript_interpreter.opstack).is_err()
+        {
+            // The line restored a save made before the checkpoint, which
+            // ended the checkpoint's save as well.
+            undone.push("virtual memory stays as the line's restore left it".to_string());
+            postscript_interpreter.dict.set_frames(self.frames.clone());
+        }
+
+        // Definitions made in the dictionaries that were on the stack.
+        let mut names = Vec::new();
+        for (frame, later) in self.frames.iter().zip(&changed)
+        {
+            let contents = frame.borrow();
+            for (name, value) in later.iter()
+            {
+                if !contents.get(name).is_some_and(|old| same_value(old, value))
+                {
+                    names.push(name.clone());
+                }
+            }
+        }
+
+        if !names.is_empty()
+        {
+            names.sort();
+            names.dedup();
+            undone.push(format!("undid definitions of {}", names.join(" ")));
+        }
+
+        if others > 0
+        {
+            undone.push(format!("undid changes to {} other dictionaries and strings", others));
+        }
+
+        if undone.is_empty()
+        {
+            return "Rolled back: nothing had changed".to_string();
+        }
+
+        format!("Rolled back: {}", undone.join("; "))
+    }
+}
+
+// True when two values are the same object, or equal simple values. Only
+// used to tell which entries a line changed.
+fn same_value(a: &Value, b: &Value) -> bool
+{
+    match (a, b)
+    {
+        (Value::Int(x), Value::Int(y)) => x == y,
+        (Value::Real(x), Value::Real(y)) => x == y,
+        (Value::Bool(x), Value::Bool(y)) => x == y,
+        (Value::Name(x), Value::Name(y)) | (Value::ExecName(x), Value::ExecName(y)) => x == y,
+        (Value::Str(x), Value::Str(y)) => x.same_object(y),
+        (Value::Dict(x), Value::Dict(y)) => Rc::ptr_eq(x, y),
+        (Value::Procedure(x), Value::Procedure(y)) => Rc::ptr_eq(&x.body, &y.body),
+        (Value::Save(x), Value::Save(y)) => x == y,
+        (Value::Mark, Value::Mark) => true,
+        _ => false,
+    }
+}
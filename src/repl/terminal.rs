@@ -0,0 +1,247 @@
+// -----------------------------------------------------------------------------
+// File: terminal.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Switches the terminal in and out of raw mode, where every key press is
+// delivered straight to the program without echo or line buffering, and
+// decodes the key presses it then delivers.
+// -----------------------------------------------------------------------------
+
+use std::io::{self, Read};
+
+// Keeps the terminal in raw mode for as long as it is alive and puts the
+// previous settings back when dropped, even if reading a line fails.
+pub struct RawMode
+{
+    // The settings the terminal had before raw mode was turned on.
+    saved: termios::Settings,
+}
+
+impl RawMode
+{
+    // Turns raw mode on. Fails if stdin is not a terminal or the platform
+    // has no termios, the caller then falls back to plain line input.
+    pub fn enable() -> io::Result<RawMode>
+    {
+        let saved = termios::get()?;
+        termios::set(&termios::make_raw(&saved))?;
+
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode
+{
+    fn drop(&mut self)
+    {
+        let _ = termios::set(&self.saved);
+    }
+}
+
+// The terminal settings of stdin, read and written through the C
+// library's termios functions. Switching modes this way is just a system
+// call, cheap enough to do around every line read.
+#[cfg(unix)]
+mod termios
+{
+    use std::io;
+
+    // struct termios is laid out differently on every platform. Nothing
+    // here looks inside it, so it is kept as a block of bytes big and
+    // aligned enough to hold any of them.
+    #[derive(Clone, Copy)]
+    #[repr(C, align(8))]
+    pub struct Settings([u8; 256]);
+
+    extern "C"
+    {
+        fn tcgetattr(fd: i32, settings: *mut Settings) -> i32;
+        fn tcsetattr(fd: i32, actions: i32, settings: *const Settings) -> i32;
+        fn cfmakeraw(settings: *mut Settings);
+    }
+
+    const STDIN: i32 = 0;
+
+    // Apply the change right away, the same value on every platform.
+    const TCSANOW: i32 = 0;
+
+    pub fn get() -> io::Result<Settings>
+    {
+        let mut settings = Settings([0; 256]);
+
+        // tcgetattr fills in at most a struct termios, which fits.
+        match unsafe { tcgetattr(STDIN, &mut settings) }
+        {
+            0 => Ok(settings),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    pub fn set(settings: &Settings) -> io::Result<()>
+    {
+        // The settings came from tcgetattr, possibly changed by cfmakeraw.
+        match unsafe { tcsetattr(STDIN, TCSANOW, settings) }
+        {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    // The settings with echo, line buffering, signals and output
+    // processing all turned off.
+    pub fn make_raw(settings: &Settings) -> Settings
+    {
+        let mut raw = *settings;
+        unsafe { cfmakeraw(&mut raw) };
+        raw
+    }
+}
+
+// Without termios there is no raw mode and the REPL reads plain lines.
+#[cfg(not(unix))]
+mod termios
+{
+    use std::io;
+
+    #[derive(Clone, Copy)]
+    pub struct Settings;
+
+    pub fn get() -> io::Result<Settings>
+    {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "no termios"))
+    }
+
+    pub fn set(_: &Settings) -> io::Result<()>
+    {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "no termios"))
+    }
+
+    pub fn make_raw(settings: &Settings) -> Settings
+    {
+        *settings
+    }
+}
+
+// A key press decoded from the bytes the terminal sends.
+#[derive(Debug, PartialEq)]
+pub enum Key
+{
+    Char(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+
+    // Ctrl and a letter, for example Ctrl('c').
+    Ctrl(char),
+
+    // Anything else, such as function keys.
+    Unknown,
+}
+
+// Reads one key press from stdin, None at end of input.
+pub fn read_key(input: &mut impl Read) -> io::Result<Option<Key>>
+{
+    let byte = match read_byte(input)?
+    {
+        Some(byte) => byte,
+        None => return Ok(None),
+    };
+
+    let key = match byte
+    {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b => read_escape(input)?,
+        0x01..=0x1a => Key::Ctrl((b'a' + byte - 1) as char),
+        0x00..=0x1f => Key::Unknown,
+        0x20..=0x7e => Key::Char(byte as char),
+
+        // The first byte of a UTF-8 sequence says how many follow.
+        _ =>
+        {
+            let length = match byte
+            {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => return Ok(Some(Key::Unknown)),
+            };
+
+            let mut bytes = vec![byte];
+            for _ in 1..length
+            {
+                match read_byte(input)?
+                {
+                    Some(next) => bytes.push(next),
+                    None => break,
+                }
+            }
+
+            match String::from_utf8(bytes).ok().and_then(|text| text.chars().next())
+            {
+                Some(c) => Key::Char(c),
+                None => Key::Unknown,
+            }
+        }
+    };
+
+    Ok(Some(key))
+}
+
+// Decodes the rest of an escape sequence, ESC [ or ESC O followed by
+// optional digits and a final letter or ~.
+fn read_escape(input: &mut impl Read) -> io::Result<Key>
+{
+    match read_byte(input)?
+    {
+        Some(b'[') | Some(b'O') => {}
+        _ => return Ok(Key::Unknown),
+    }
+
+    let mut parameter = String::new();
+    loop
+    {
+        let byte = match read_byte(input)?
+        {
+            Some(byte) => byte,
+            None => return Ok(Key::Unknown),
+        };
+
+        if byte.is_ascii_digit() || byte == b';'
+        {
+            parameter.push(byte as char);
+            continue;
+        }
+
+        return Ok(match (byte, parameter.as_str())
+        {
+            (b'A', _) => Key::Up,
+            (b'B', _) => Key::Down,
+            (b'C', _) => Key::Right,
+            (b'D', _) => Key::Left,
+            (b'H', _) | (b'~', "1") | (b'~', "7") => Key::Home,
+            (b'F', _) | (b'~', "4") | (b'~', "8") => Key::End,
+            (b'~', "3") => Key::Delete,
+            _ => Key::Unknown,
+        });
+    }
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>>
+{
+    let mut byte = [0u8; 1];
+    match input.read(&mut byte)?
+    {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
@@ -1,5 +1,5 @@
 // -----------------------------------------------------------------------------
-// File: boolean_operation_tests.rs
+// File: IO_operation_tests.rs
 // Author: Quinn Bankhead
 // Project: PostScript Interpreter (CptS 355 - Mini Project)
 // Description:
@@ -7,16 +7,19 @@
 // -----------------------------------------------------------------------------
 
 use ps_interpreter::{Interpreter, ScopeMode};
+use ps_interpreter::interpreter::output::OutputBuffer;
+use ps_interpreter::interpreter::value::format_value;
 
-// Normal test case to ensure print functionality works correctly
+// Normal test case to ensure print writes the bytes of the string
 #[test]
 fn test_print_normal()
 {
     let mut i = Interpreter::new(ScopeMode::Dynamic);
+    let output = i.capture_output();
 
-    // Should not panic
-    let res = i.interpret("(hello) print");
+    let res = i.interpret("(hello) print (\\n\\377) print");
     assert!(res.is_ok());
+    assert_eq!(output.text(), "hello\n\u{fffd}");
 
     // Stack should now be empty
     assert_eq!(i.len(), 0);
@@ -38,8 +41,11 @@ fn test_equals_normal()
 {
     let mut i = Interpreter::new(ScopeMode::Dynamic);
 
-    let res = i.interpret("42 =");
+    let output = i.capture_output();
+
+    let res = i.interpret("42 = (text) = /name = 2.5 = << >> =");
     assert!(res.is_ok());
+    assert_eq!(output.text(), "42\ntext\nname\n2.5\n--dict--\n");
 
     assert_eq!(i.len(), 0);
 }
@@ -50,9 +56,119 @@ fn test_literal_equals_normal()
 {
     let mut i = Interpreter::new(ScopeMode::Dynamic);
 
-    let res = i.interpret("(hello) ==");
+    let output = i.capture_output();
+
+    let res = i.interpret("(hello) == (a\\(b) == { 1 add } ==");
     assert!(res.is_ok());
+    assert_eq!(output.text(), "(hello)\n(a\\(b)\n{1 add}\n");
 
     assert_eq!(i.len(), 0);
 }
 
+
+// Normal test case to ensure stack and pstack leave the stack alone
+#[test]
+fn test_stack_and_pstack_normal()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+    let output = i.capture_output();
+
+    let res = i.interpret("1 (two) /three stack pstack");
+    assert!(res.is_ok());
+    assert_eq!(output.take(), "three\ntwo\n1\n/three\n(two)\n1\n");
+    assert_eq!(output.text(), "");
+
+    assert_eq!(i.len(), 3);
+}
+
+// Normal test case to ensure == writes procedures and dictionaries so
+// they read back as the same value
+#[test]
+fn test_format_value_round_trip()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("{ 1 (a\\)) /n { 2 mul } if } << /b 2.5 /a << /c true >> >>").unwrap();
+    assert_eq!(i.stack_listing(), "<< /a << /c true >> /b 2.5 >>\n{1 (a\\)) /n {2 mul} if}");
+
+    // Running the printed text again gives the same listing.
+    let listing = i.stack_listing();
+    let mut again = Interpreter::new(ScopeMode::Dynamic);
+    again.interpret(&listing.lines().rev().collect::<Vec<&str>>().join(" ")).unwrap();
+    assert_eq!(again.stack_listing(), listing);
+}
+
+// Edge test case to ensure a dictionary that contains itself is cut short
+#[test]
+fn test_format_value_cycle()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("/d 1 dict def d /self d put d").unwrap();
+    assert_eq!(i.stack_listing(), "<< /self --dict-- >>");
+}
+
+// Edge test case to ensure deep nesting stops at the depth limit
+#[test]
+fn test_format_value_depth_limit()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("{{{{{{{{{{{{ 1 }}}}}}}}}}}}").unwrap();
+    assert_eq!(format_value(&i.opstack_snapshot()[0]), "{{{{{{{{{{--procedure--}}}}}}}}}}");
+
+    // Unreadable objects are not shown either.
+    i.interpret("clear { 1 } noaccess").unwrap();
+    assert_eq!(i.stack_listing(), "--procedure--");
+}
+
+// Normal test case to ensure output goes to whatever sink is set
+#[test]
+fn test_set_output()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+    let buffer = OutputBuffer::new();
+    i.set_output(Box::new(buffer.clone()));
+
+    i.interpret("(to the buffer) print").unwrap();
+    assert_eq!(buffer.text(), "to the buffer");
+}
+
+// Edge test case to ensure a failing sink is reported as an ioerror
+#[test]
+fn test_output_error()
+{
+    struct Broken;
+
+    impl std::io::Write for Broken
+    {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize>
+        {
+            Err(std::io::Error::other("broken"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()>
+        {
+            Ok(())
+        }
+    }
+
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+    i.set_output(Box::new(Broken));
+
+    assert_eq!(i.interpret("1 =").unwrap_err(), "/ioerror in --=--");
+}
+
+// Edge test case to ensure =, == and the stack listing do not show the
+// contents of strings that can not be read
+#[test]
+fn test_unreadable_string_output()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    let output = i.capture_output();
+
+    i.interpret("(secret) noaccess dup = dup == (code) executeonly == (open) readonly ==").unwrap();
+    assert_eq!(output.text(), "--nostringval--\n--nostringval--\n--nostringval--\n(open)\n");
+    assert_eq!(i.stack_listing(), "--nostringval--");
+}
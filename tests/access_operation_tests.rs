@@ -0,0 +1,172 @@
+// -----------------------------------------------------------------------------
+// File: access_operation_tests.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Unit tests for access attributes on strings, dictionaries and procedures.
+// -----------------------------------------------------------------------------
+
+use ps_interpreter::{Interpreter, ScopeMode};
+use ps_interpreter::interpreter::value::Value;
+
+// Normal test case to ensure new objects can be read and written
+#[test]
+fn test_checks_on_new_objects()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("(abc) rcheck (abc) wcheck 1 dict wcheck {1} rcheck").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Bool(true), Value::Bool(true), Value::Bool(true), Value::Bool(true)] => {}
+        _ => panic!("expected four trues"),
+    }
+}
+
+// Normal test case to ensure readonly strings can be read but not written
+#[test]
+fn test_readonly_string()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/s (abc) readonly def s rcheck s wcheck s 0 get").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Bool(true), Value::Bool(false), Value::Int(97)] => {}
+        _ => panic!("expected true false 97"),
+    }
+
+    let result = postscript_interpreter.interpret("s 0 (x) putinterval");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+
+    let result = postscript_interpreter.interpret("s 0 120 put");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+
+    let result = postscript_interpreter.interpret("42 s cvs");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+}
+
+// Normal test case to ensure readonly only applies to that string object
+#[test]
+fn test_readonly_is_per_string_object()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/s (abc) def /r s readonly def s 0 (x) putinterval r").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Str(s) => assert_eq!(s, "xbc"),
+        _ => panic!("expected xbc"),
+    }
+}
+
+// Normal test case to ensure a readonly dictionary rejects def and put
+#[test]
+fn test_readonly_dict()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/lib 5 dict def lib /x 1 put lib readonly pop").unwrap();
+
+    // The dictionary itself is read only, not just the copy readonly returned
+    let result = postscript_interpreter.interpret("lib /x 2 put");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+
+    let result = postscript_interpreter.interpret("lib begin /x 2 def");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+
+    postscript_interpreter.interpret("clear end lib /x get").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(1) => {}
+        _ => panic!("expected x to still be 1"),
+    }
+}
+
+// Normal test case to ensure executeonly procedures still run
+#[test]
+fn test_executeonly_procedure()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("true {1 2 add} executeonly if").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(3) => {}
+        _ => panic!("expected 3"),
+    }
+}
+
+// Edge test case to ensure noaccess objects can not be used
+#[test]
+fn test_noaccess()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("(abc) noaccess 0 get");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+
+    let result = postscript_interpreter.interpret("true {1} noaccess if");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+
+    let result = postscript_interpreter.interpret("1 dict noaccess begin");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+
+    let result = postscript_interpreter.interpret("(abc) executeonly length");
+    assert_eq!(result.unwrap_err(), "/invalidaccess in --length--");
+
+    let result = postscript_interpreter.interpret("1 dict noaccess length");
+    assert_eq!(result.unwrap_err(), "/invalidaccess in --length--");
+}
+
+// Edge test case to ensure comparisons and cvs do not read strings
+// without read access, the same as eq
+#[test]
+fn test_unreadable_string_compare_and_cvs()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("(abc) noaccess (abd) lt");
+    assert_eq!(result.unwrap_err(), "/invalidaccess in --lt--");
+
+    let result = postscript_interpreter.interpret("clear (abc) (abd) executeonly ge");
+    assert_eq!(result.unwrap_err(), "/invalidaccess in --ge--");
+
+    let result = postscript_interpreter.interpret("clear (secret) noaccess 10 string cvs");
+    assert_eq!(result.unwrap_err(), "/invalidaccess in --cvs--");
+
+    // Read only strings can still be compared and converted
+    postscript_interpreter.interpret("clear (abc) readonly (abd) gt (xy) readonly 2 string cvs").unwrap();
+    assert_eq!(postscript_interpreter.stack_listing(), "(xy)\nfalse");
+}
+
+// Edge test case to ensure access can not be raised again
+#[test]
+fn test_access_can_not_increase()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("(abc) noaccess readonly");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+}
+
+// Edge test case to ensure simple values have no access attributes
+#[test]
+fn test_access_type_error()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("5 readonly");
+    assert_eq!(result.unwrap_err().name, "typecheck");
+
+    let result = postscript_interpreter.interpret("5 rcheck");
+    assert_eq!(result.unwrap_err().name, "typecheck");
+
+    let result = postscript_interpreter.interpret("1 dict executeonly");
+    assert_eq!(result.unwrap_err().name, "typecheck");
+}
@@ -24,13 +24,35 @@ fn test_eq_normal()
     }
 }
 
-// Edge test case for ensuring eq throws error on bad types
+// Edge test case for ensuring values of unrelated types are not equal
 #[test]
-fn test_eq_type_error()
+fn test_eq_mismatched_types()
 {
     let mut i = Interpreter::new(ScopeMode::Dynamic);
-    let result = i.interpret("5 true eq");
-    assert!(result.is_err());
+
+    i.interpret("5 true eq (5) 5 eq 1 dict /x ne").unwrap();
+
+    match &i.opstack_snapshot()[..]
+    {
+        [Value::Bool(false), Value::Bool(false), Value::Bool(true)] => {},
+        _ => panic!("expected false false true"),
+    }
+}
+
+// Normal test case for ensuring dictionaries and procedures are only
+// equal to themselves
+#[test]
+fn test_eq_composite_identity()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("/d 1 dict def d d eq d 1 dict eq { 1 } dup eq { 1 } { 1 } eq").unwrap();
+
+    match &i.opstack_snapshot()[..]
+    {
+        [Value::Bool(true), Value::Bool(false), Value::Bool(true), Value::Bool(false)] => {},
+        _ => panic!("expected true false true false"),
+    }
 }
 
 // Normal test case for ensuring ne functionality works correctly
@@ -87,6 +109,106 @@ fn test_lt_normal()
     }
 }
 
+// Normal test case for ensuring ge functionality works correctly
+#[test]
+fn test_ge_normal()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("3 3 ge 2 3 ge").unwrap();
+
+    match &i.opstack_snapshot()[..]
+    {
+        [Value::Bool(true), Value::Bool(false)] => {},
+        _ => panic!("expected true false"),
+    }
+}
+
+// Normal test case for ensuring le functionality works correctly
+#[test]
+fn test_le_normal()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("3 3 le 4 3 le").unwrap();
+
+    match &i.opstack_snapshot()[..]
+    {
+        [Value::Bool(true), Value::Bool(false)] => {},
+        _ => panic!("expected true false"),
+    }
+}
+
+// Normal test case for ensuring comparisons work across ints and reals
+#[test]
+fn test_compare_int_real_mix()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("2 1.5 gt 1.5 2 lt 2.0 2 ge 2 2.5 le").unwrap();
+
+    match &i.opstack_snapshot()[..]
+    {
+        [Value::Bool(true), Value::Bool(true), Value::Bool(true), Value::Bool(true)] => {},
+        _ => panic!("expected four trues"),
+    }
+}
+
+// Normal test case for ensuring strings compare lexicographically
+#[test]
+fn test_compare_strings()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("(apple) (banana) lt (abc) (ab) gt (abc) (abc) ge (b) (a) le").unwrap();
+
+    match &i.opstack_snapshot()[..]
+    {
+        [Value::Bool(true), Value::Bool(true), Value::Bool(true), Value::Bool(false)] => {},
+        _ => panic!("expected true true true false"),
+    }
+}
+
+// Edge test case for ensuring strings and numbers can not be ordered
+#[test]
+fn test_compare_string_number_type_error()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+    let result = i.interpret("(a) 1 ge");
+    assert!(result.is_err());
+}
+
+// Normal test case for ensuring eq compares ints and reals numerically
+#[test]
+fn test_eq_int_real()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("1 1.0 eq 1 1.5 ne").unwrap();
+
+    match &i.opstack_snapshot()[..]
+    {
+        [Value::Bool(true), Value::Bool(true)] => {},
+        _ => panic!("expected true true"),
+    }
+}
+
+// Normal test case for ensuring eq treats strings and names with the
+// same text as equal
+#[test]
+fn test_eq_string_name()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("(abc) /abc eq /abc /abd eq /x /x eq").unwrap();
+
+    match &i.opstack_snapshot()[..]
+    {
+        [Value::Bool(true), Value::Bool(false), Value::Bool(true)] => {},
+        _ => panic!("expected true false true"),
+    }
+}
+
 // Normal test case for ensuring and functionality works correctly
 #[test]
 fn test_and_normal()
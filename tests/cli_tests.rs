@@ -0,0 +1,175 @@
+// -----------------------------------------------------------------------------
+// File: cli_tests.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Tests running the interpreter binary on files, -c code and piped stdin.
+// -----------------------------------------------------------------------------
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+// Runs the interpreter with the given arguments and stdin contents.
+fn run(args: &[&str], stdin: &str) -> Output
+{
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ps_interpreter"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+// A file in the temporary directory that is removed again when the
+// test is done with it, whether the test passes or not.
+struct TempFile
+{
+    path: String,
+}
+
+impl TempFile
+{
+    fn new(name: &str) -> Self
+    {
+        let path = std::env::temp_dir().join(format!("ps_interpreter_{}_{}", std::process::id(), name));
+        Self { path: path.to_string_lossy().into_owned() }
+    }
+}
+
+impl Drop for TempFile
+{
+    fn drop(&mut self)
+    {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Writes a temporary PostScript file.
+fn script(name: &str, contents: &[u8]) -> TempFile
+{
+    let file = TempFile::new(&format!("{}.ps", name));
+    fs::write(&file.path, contents).unwrap();
+    file
+}
+
+// Normal test case to ensure -c runs code and exits successfully
+#[test]
+fn test_code_option()
+{
+    let output = run(&["-c", "1 2 add ="], "");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+}
+
+// Normal test case to ensure files run in order with shared state
+#[test]
+fn test_multiple_files()
+{
+    let first = script("first", b"/x 20 def\n");
+    let second = script("second", b"x 22 add =\n");
+
+    let output = run(&[&first.path, &second.path], "");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+}
+
+// Normal test case to ensure a file with a %!PS header and comments runs,
+// and bytes that are not UTF-8 reach its strings unchanged
+#[test]
+fn test_postscript_file()
+{
+    let file = script("header", b"%!PS-Adobe-3.0\n% a comment\n1 2 add = % the sum\n(\xff\xfe) print\n");
+
+    let output = run(&[&file.path], "");
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"3\n\xff\xfe");
+}
+
+// Normal test case to ensure a program piped into stdin is run
+#[test]
+fn test_stdin_program()
+{
+    let output = run(&[], "/f {\n  2 mul\n} def\n21 f =\n");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+}
+
+// Error test case to ensure an uncaught error gives a non-zero exit status
+#[test]
+fn test_error_exit_status()
+{
+    let output = run(&["-c", "1 (a) add", "-c", "(not reached) ="], "");
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Error"));
+    assert!(output.stdout.is_empty());
+}
+
+// Error test case to ensure a missing file is reported
+#[test]
+fn test_missing_file()
+{
+    let output = run(&["/no/such/file.ps"], "");
+
+    assert!(!output.status.success());
+}
+
+// Normal test case to ensure --trace writes the log to stderr only
+#[test]
+fn test_trace_option()
+{
+    let output = run(&["--trace", "-c", "/f { 1 dict begin /x 2 def end } def f x ="], "");
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let log = String::from_utf8_lossy(&output.stderr);
+    assert!(log.starts_with("[debug] def /f {1 dict begin /x 2 def end}\n[trace] call f (dynamic)\n[debug] begin"));
+    assert!(log.contains("[trace] return from f\n"));
+}
+
+// Error test case to ensure --log needs a known level
+#[test]
+fn test_log_option_error()
+{
+    let output = run(&["--log", "loud", "-c", "1 ="], "");
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+}
+
+// Normal test case to ensure --trace-exec traces tokens on stderr
+#[test]
+fn test_trace_exec_option()
+{
+    let output = run(&["--trace-exec", "-c", "1 2 add ="], "");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "3\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[exec] 0 add  [1 2]\n[exec] 0 add  -> [3]\n"));
+}
+
+// Normal test case to ensure --flamegraph reports on stderr and writes
+// the collapsed stacks
+#[test]
+fn test_flamegraph_option()
+{
+    let stacks_file = TempFile::new("stacks.txt");
+    let output = run(&["--flamegraph", &stacks_file.path, "-c", "/f { 2 mul } def 21 f ="], "");
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "42\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("  --mul--\n"));
+
+    let stacks = fs::read_to_string(&stacks_file.path).unwrap();
+    assert!(stacks.lines().any(|line| line.starts_with("f;--mul-- ")));
+}
@@ -0,0 +1,182 @@
+// -----------------------------------------------------------------------------
+// File: conversion_operation_tests.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Unit tests for the type and conversion operations.
+// -----------------------------------------------------------------------------
+
+use ps_interpreter::{Interpreter, ScopeMode};
+use ps_interpreter::interpreter::value::Value;
+
+// Runs the program and returns the text of the string on top of the stack.
+fn top_string(program: &str) -> String
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret(program).unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Str(s) => s.to_string_lossy(),
+        _ => panic!("expected a string"),
+    }
+}
+
+// Normal test case to ensure type names every kind of value
+#[test]
+fn test_type_normal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("1 type 1.5 type true type (a) type /a type 1 dict type {1} type").unwrap();
+
+    let names: Vec<String> = postscript_interpreter
+        .opstack_snapshot()
+        .into_iter()
+        .map(|v| match v
+        {
+            Value::Name(n) => n,
+            _ => panic!("type should push a name"),
+        })
+        .collect();
+
+    assert_eq!(names, ["integertype", "realtype", "booleantype", "stringtype", "nametype", "dicttype", "arraytype"]);
+}
+
+// Normal test case to ensure cvi truncates reals and parses strings
+#[test]
+fn test_cvi_normal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("-3.7 cvi ( 42 ) cvi (2.9) cvi").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    match &stack[..]
+    {
+        [Value::Int(-3), Value::Int(42), Value::Int(2)] => {}
+        _ => panic!("expected -3 42 2"),
+    }
+}
+
+// Edge test case to ensure cvi reports typecheck and rangecheck
+#[test]
+fn test_cvi_errors()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let typecheck = postscript_interpreter.interpret("true cvi").unwrap_err();
+    assert_eq!(typecheck.name, "typecheck");
+
+    let rangecheck = postscript_interpreter.interpret("1e20 cvi").unwrap_err();
+    assert_eq!(rangecheck.name, "rangecheck");
+}
+
+// Normal test case to ensure cvr converts integers and strings
+#[test]
+fn test_cvr_normal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("3 cvr (2.5) cvr").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    match &stack[..]
+    {
+        [Value::Real(a), Value::Real(b)] =>
+        {
+            assert_eq!(*a, 3.0);
+            assert_eq!(*b, 2.5);
+        }
+        _ => panic!("expected two reals"),
+    }
+}
+
+// Normal test case to ensure cvn turns a string into a name
+#[test]
+fn test_cvn_normal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("(hello) cvn").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Name(n) => assert_eq!(n, "hello"),
+        _ => panic!("expected /hello"),
+    }
+}
+
+// Edge test case to ensure cvn rejects numbers
+#[test]
+fn test_cvn_type_error()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("5 cvn");
+
+    assert_eq!(result.unwrap_err().name, "typecheck");
+}
+
+// Normal test case to ensure cvs writes text for each kind of value
+#[test]
+fn test_cvs_normal()
+{
+    assert_eq!(top_string("123 10 string cvs"), "123");
+    assert_eq!(top_string("true 10 string cvs"), "true");
+    assert_eq!(top_string("/abc 10 string cvs"), "abc");
+    assert_eq!(top_string("{1} 20 string cvs"), "--nostringval--");
+}
+
+// Normal test case to ensure reals are formatted the way PostScript does
+#[test]
+fn test_cvs_real_formatting()
+{
+    assert_eq!(top_string("2.0 20 string cvs"), "2.0");
+    assert_eq!(top_string("3.14159265 20 string cvs"), "3.14159");
+    assert_eq!(top_string("0.5 20 string cvs"), "0.5");
+    assert_eq!(top_string("1e10 20 string cvs"), "1.0e+10");
+    assert_eq!(top_string("1234567.0 20 string cvs"), "1.23457e+06");
+    assert_eq!(top_string("0.0001 20 string cvs"), "0.0001");
+    assert_eq!(top_string("0.00001 20 string cvs"), "1.0e-05");
+}
+
+// Normal test case to ensure cvs result shares storage with its operand
+#[test]
+fn test_cvs_shares_storage()
+{
+    assert_eq!(top_string("/buf (xxxxx) def 42 buf cvs pop buf"), "42xxx");
+}
+
+// Edge test case to ensure cvs reports rangecheck when the string is too small
+#[test]
+fn test_cvs_range_error()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("12345 2 string cvs");
+
+    assert_eq!(result.unwrap_err().name, "rangecheck");
+}
+
+// Normal test case to ensure cvrs writes numbers in other radixes
+#[test]
+fn test_cvrs_normal()
+{
+    assert_eq!(top_string("255 16 10 string cvrs"), "FF");
+    assert_eq!(top_string("5 2 10 string cvrs"), "101");
+    assert_eq!(top_string("-1 16 10 string cvrs"), "FFFFFFFF");
+    assert_eq!(top_string("2.5 10 10 string cvrs"), "2.5");
+}
+
+// Edge test case to ensure cvrs rejects an invalid radix
+#[test]
+fn test_cvrs_range_error()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("10 37 10 string cvrs");
+
+    assert_eq!(result.unwrap_err().name, "rangecheck");
+}
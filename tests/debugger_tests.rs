@@ -0,0 +1,218 @@
+// -----------------------------------------------------------------------------
+// File: debugger_tests.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Unit tests for the step debugger, breakpoints and source positions.
+// -----------------------------------------------------------------------------
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use ps_interpreter::{Interpreter, ScopeMode};
+use ps_interpreter::interpreter::debug::{Breakpoint, DebugConsole, Debugger, StepMode};
+use ps_interpreter::interpreter::tokenizer::tokenize_in;
+use ps_interpreter::repl::ReplOptions;
+use ps_interpreter::repl::commands::meta_command;
+
+// A console that answers with a fixed list of commands and records
+// everything the debugger shows.
+struct ScriptedConsole
+{
+    commands: VecDeque<String>,
+    shown: Rc<RefCell<Vec<String>>>,
+}
+
+impl DebugConsole for ScriptedConsole
+{
+    fn read_command(&mut self, _prompt: &str) -> Option<String>
+    {
+        self.commands.pop_front()
+    }
+
+    fn show(&mut self, text: &str)
+    {
+        self.shown.borrow_mut().push(text.to_string());
+    }
+}
+
+// A procedure over several lines, as it would be written in a file.
+const PROGRAM: &str = "/sq { dup mul } def\n/f {\n  sq\n  1 add\n} def\n";
+
+// An interpreter with PROGRAM loaded and a debugger that will run the
+// given commands, and the list of what the debugger showed.
+fn debugging(commands: &[&str]) -> (Interpreter, Rc<RefCell<Vec<String>>>)
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.interpret_source(PROGRAM.as_bytes(), "prog.ps").unwrap();
+
+    let shown = Rc::new(RefCell::new(Vec::new()));
+    let console = ScriptedConsole
+    {
+        commands: commands.iter().map(|command| command.to_string()).collect(),
+        shown: Rc::clone(&shown),
+    };
+    postscript_interpreter.host.debugger = Some(Debugger::new(Box::new(console)));
+
+    (postscript_interpreter, shown)
+}
+
+// Normal test case to ensure tokens know the line and column they start at
+#[test]
+fn test_token_positions()
+{
+    let tokens = tokenize_in(b"1 2\n  { add }\n(a\nb) x", false, "prog.ps").unwrap();
+    let positions: Vec<String> = tokens.iter().map(|token| token.position().to_string()).collect();
+
+    assert_eq!(positions, ["prog.ps:1:1", "prog.ps:1:3", "prog.ps:2:3", "prog.ps:3:1", "prog.ps:4:4"]);
+}
+
+// Normal test case to ensure breakpoint specifications are understood
+#[test]
+fn test_breakpoint_parse()
+{
+    assert_eq!(Breakpoint::parse("12"), Some(Breakpoint::Line { source: None, line: 12 }));
+    assert_eq!(Breakpoint::parse("prog.ps:3"), Some(Breakpoint::Line { source: Some("prog.ps".to_string()), line: 3 }));
+    assert_eq!(Breakpoint::parse("sq"), Some(Breakpoint::Name("sq".to_string())));
+    assert_eq!(Breakpoint::parse("a b"), None);
+}
+
+// Normal test case to ensure a name breakpoint stops before the call and
+// the stacks can be inspected there
+#[test]
+fn test_name_breakpoint()
+{
+    let (mut postscript_interpreter, shown) = debugging(&["stack", "where", "c"]);
+    postscript_interpreter.host.debugger.as_mut().unwrap().add_breakpoint(Breakpoint::parse("sq").unwrap());
+
+    postscript_interpreter.interpret("3 f").unwrap();
+
+    assert_eq!(*shown.borrow(), ["breakpoint 1, at sq (prog.ps:3:3), depth 1", "3", "at sq (prog.ps:3:3), depth 1"]);
+    assert_eq!(postscript_interpreter.stack_listing(), "10");
+}
+
+// Normal test case to ensure a line breakpoint stops once per visit
+#[test]
+fn test_line_breakpoint()
+{
+    let (mut postscript_interpreter, shown) = debugging(&["c", "c"]);
+    postscript_interpreter.host.debugger.as_mut().unwrap().add_breakpoint(Breakpoint::parse("prog.ps:4").unwrap());
+
+    postscript_interpreter.interpret("3 f 4 f").unwrap();
+
+    assert_eq!(*shown.borrow(), ["breakpoint 1, at 1 (prog.ps:4:3), depth 1", "breakpoint 1, at 1 (prog.ps:4:3), depth 1"]);
+}
+
+// Normal test case to ensure step goes into procedures, next runs over
+// them and out runs to the end of the current one
+#[test]
+fn test_stepping()
+{
+    let (mut postscript_interpreter, shown) = debugging(&["s", "s", "n", "s", "o", "c"]);
+    postscript_interpreter.host.debugger.as_mut().unwrap().mode = StepMode::Into;
+
+    postscript_interpreter.interpret("3 f 5").unwrap();
+
+    assert_eq!(*shown.borrow(),
+    [
+        "at 3 (-:1:1), depth 0",
+        "at f (-:1:3), depth 0",
+        "at sq (prog.ps:3:3), depth 1",
+        "at 1 (prog.ps:4:3), depth 1",
+        "at add (prog.ps:4:5), depth 1",
+        "at 5 (-:1:5), depth 0",
+    ]);
+    assert_eq!(postscript_interpreter.stack_listing(), "5\n10");
+}
+
+// Normal test case to ensure an empty command repeats the last step
+#[test]
+fn test_repeat_step()
+{
+    let (mut postscript_interpreter, shown) = debugging(&["n", "", "c"]);
+    postscript_interpreter.host.debugger.as_mut().unwrap().mode = StepMode::Into;
+
+    postscript_interpreter.interpret("3 f 5").unwrap();
+
+    assert_eq!(shown.borrow().len(), 3);
+    assert_eq!(shown.borrow()[2], "at 5 (-:1:5), depth 0");
+}
+
+// Edge test case to ensure quit stops the program with an interrupt
+#[test]
+fn test_quit()
+{
+    let (mut postscript_interpreter, _) = debugging(&["quit"]);
+    postscript_interpreter.host.debugger.as_mut().unwrap().mode = StepMode::Into;
+
+    assert_eq!(postscript_interpreter.interpret("3 f").unwrap_err(), "/interrupt in --debugger--");
+    assert_eq!(postscript_interpreter.stack_listing(), "");
+}
+
+// Normal test case to ensure breakpoints can be added, listed and deleted
+// while stopped
+#[test]
+fn test_breakpoint_commands()
+{
+    let (mut postscript_interpreter, shown) = debugging(&["break add", "break 2", "delete 1", "delete 7", "break", "bogus", "c"]);
+    postscript_interpreter.host.debugger.as_mut().unwrap().mode = StepMode::Into;
+
+    postscript_interpreter.interpret("1").unwrap();
+
+    assert_eq!(*shown.borrow(),
+    [
+        "at 1 (-:1:1), depth 0",
+        "breakpoint 1 at add",
+        "breakpoint 2 at line 2",
+        "deleted breakpoint 1 at add",
+        "Error: no breakpoint '7'",
+        "1 line 2",
+        "Error: unknown debugger command 'bogus', try help",
+    ]);
+}
+
+// Normal test case to ensure :step runs code stepping from its first
+// token and the REPL commands manage breakpoints
+#[test]
+fn test_repl_debug_commands()
+{
+    let (mut postscript_interpreter, shown) = debugging(&["c"]);
+    let mut options = ReplOptions::default();
+
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut options, ":debug"), "debugger is on");
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut options, ":break f"), "breakpoint 1 at f");
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut options, ":delete 1"), "deleted breakpoint 1 at f");
+
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut options, ":step 2 f"), "5");
+    assert_eq!(*shown.borrow(), ["at 2 (-:1:1), depth 0"]);
+    assert_eq!(postscript_interpreter.host.debugger.as_ref().unwrap().mode, StepMode::Continue);
+
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut options, ":debug off"), "debugger is now off");
+    assert!(meta_command(&mut postscript_interpreter, &mut options, ":delete 1").starts_with("Error"));
+}
+
+// Normal test case to ensure backtrace lists the procedures running
+#[test]
+fn test_backtrace_command()
+{
+    let (mut postscript_interpreter, shown) = debugging(&["bt", "c"]);
+    postscript_interpreter.host.debugger.as_mut().unwrap().add_breakpoint(Breakpoint::parse("mul").unwrap());
+
+    postscript_interpreter.interpret("3 f").unwrap();
+
+    assert_eq!(shown.borrow()[1], "   sq at prog.ps:1:11\n   f at prog.ps:3:3\n   top level at -:1:3");
+}
+
+// Normal test case to ensure backtrace lists the operators that ran a
+// procedure as well
+#[test]
+fn test_backtrace_command_operators()
+{
+    let (mut postscript_interpreter, shown) = debugging(&["bt", "c"]);
+    postscript_interpreter.host.debugger.as_mut().unwrap().add_breakpoint(Breakpoint::parse("mul").unwrap());
+
+    postscript_interpreter.interpret("true { 3 sq } if").unwrap();
+
+    assert_eq!(shown.borrow()[1], "   sq at prog.ps:1:11\n   --if-- at -:1:10\n   top level at -:1:15");
+}
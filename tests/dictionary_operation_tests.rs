@@ -68,7 +68,8 @@ fn test_begin_normal()
     let env = postscript_interpreter.dict.env();
     let borrowed = env.borrow();
 
-    assert_eq!(borrowed.len(), 2, "begin should push a new dictionary frame");
+    // globaldict and userdict are always there, begin adds a third frame
+    assert_eq!(borrowed.len(), 3, "begin should push a new dictionary frame");
 }
 
 // Edge test case for ensuring if begin doesn't access a dict value it 
@@ -93,14 +94,14 @@ fn test_end_normal()
     // Create a new dictionary and begin it
     postscript_interpreter.interpret("5 dict begin").unwrap();
 
-    // Dictionary stack should now have 2 frames
-    assert_eq!(postscript_interpreter.dict.env().borrow().len(), 2);
+    // Dictionary stack should now have 3 frames (globaldict, userdict and the new one)
+    assert_eq!(postscript_interpreter.dict.env().borrow().len(), 3);
 
     // Now end the scope
     postscript_interpreter.interpret("end").unwrap();
 
-    // Back to only 1 frame
-    assert_eq!(postscript_interpreter.dict.env().borrow().len(), 1);
+    // Back to only the 2 permanent frames
+    assert_eq!(postscript_interpreter.dict.env().borrow().len(), 2);
 }
 
 // Edge test case to ensure that if the dictionary stack
@@ -159,3 +160,51 @@ fn test_length_empty_dictionary()
 
 
 
+
+// Normal test case to ensure put and get work on dictionaries
+#[test]
+fn test_put_get_dictionary()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/d 1 dict def d /answer 42 put d /answer get").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(42) => {}
+        _ => panic!("expected 42"),
+    }
+}
+
+// Edge test case to ensure get of a missing key throws error
+#[test]
+fn test_get_dictionary_undefined()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("1 dict /missing get");
+
+    assert!(result.is_err(), "get should fail for a key that is not defined");
+}
+
+// Normal test case to ensure << >> builds a dictionary from its pairs
+#[test]
+fn test_dict_literal_normal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("1 << /a 1 (b) 2 >> dup /a get exch /b get").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    assert!(matches!(stack.as_slice(), [Value::Int(1), Value::Int(1), Value::Int(2)]));
+}
+
+// Edge test case to ensure << >> rejects an odd count and a missing mark
+#[test]
+fn test_dict_literal_errors()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    assert_eq!(postscript_interpreter.interpret("<< /a 1 /b >>").unwrap_err(), "/rangecheck in -->>--");
+    assert_eq!(postscript_interpreter.interpret("clear /a 1 >>").unwrap_err(), "/unmatchedmark in -->>--");
+}
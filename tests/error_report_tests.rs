@@ -0,0 +1,114 @@
+// -----------------------------------------------------------------------------
+// File: error_report_tests.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Unit tests for PostScript style error names and error reports.
+// -----------------------------------------------------------------------------
+
+use ps_interpreter::{Interpreter, ScopeMode};
+
+// Normal test case to ensure operator errors name the error and operator
+#[test]
+fn test_error_names()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    assert_eq!(postscript_interpreter.interpret("1 (a) add").unwrap_err(), "/typecheck in --add--");
+    assert_eq!(postscript_interpreter.interpret("clear pop").unwrap_err(), "/stackunderflow in --pop--");
+    assert_eq!(postscript_interpreter.interpret("nosuchname").unwrap_err(), "/undefined in nosuchname");
+    assert_eq!(postscript_interpreter.interpret("5 0 div").unwrap_err(), "/undefinedresult in --div--");
+    assert_eq!(postscript_interpreter.interpret("end").unwrap_err(), "/dictstackunderflow in --end--");
+}
+
+// Edge test case to ensure syntax errors are reported as syntaxerror
+#[test]
+fn test_syntax_error()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let error = postscript_interpreter.interpret("{ 1 2").unwrap_err();
+    assert_eq!(error.name, "syntaxerror");
+}
+
+// Normal test case to ensure the report shows the operand stack, with
+// the operands of the failing operator still on it, and the backtrace
+#[test]
+fn test_error_report()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/f { true { 1 (a) add } if } def").unwrap();
+    let error = postscript_interpreter.interpret("(x) 5 f").unwrap_err();
+
+    assert_eq!(postscript_interpreter.error_report(&error),
+        "Error: /typecheck in --add--\n\
+         Operand stack:\n   \
+         (x)   5   1   (a)\n\
+         Backtrace:\n   \
+         --add-- at -:1:19\n   \
+         --if-- at -:1:19\n   \
+         f at -:1:25\n   \
+         top level at -:1:7");
+}
+
+// Normal test case to ensure a failing operator leaves its operands on
+// the stack, while the operators around it keep what they used
+#[test]
+fn test_operands_kept_on_error()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    assert_eq!(postscript_interpreter.interpret("(abc) 0 -1 getinterval").unwrap_err(), "/rangecheck in --getinterval--");
+    assert_eq!(postscript_interpreter.stack_listing(), "-1\n0\n(abc)");
+
+    postscript_interpreter.interpret("clear").unwrap();
+    assert!(postscript_interpreter.interpret("7 8 { pop pop 9 clear (a) 1 add } exec").is_err());
+    assert_eq!(postscript_interpreter.stack_listing(), "1\n(a)");
+}
+
+// Normal test case to ensure the backtrace names every procedure running
+// with the position each had reached
+#[test]
+fn test_backtrace()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret_source(b"/sq { dup mul } def\n/f {\n  sq\n  1 add\n} def\n", "prog.ps").unwrap();
+    let error = postscript_interpreter.interpret_source(b"(a) f", "-c").unwrap_err();
+
+    assert!(postscript_interpreter.error_report(&error).ends_with(
+        "Backtrace:\n   \
+         --mul-- at prog.ps:1:11\n   \
+         sq at prog.ps:1:11\n   \
+         f at prog.ps:3:3\n   \
+         top level at -c:1:5"));
+
+    // Nothing is left running afterwards.
+    assert!(postscript_interpreter.call_stack.is_empty());
+}
+
+// Edge test case to ensure errors outside any procedure and syntax errors
+// get a short backtrace or none
+#[test]
+fn test_backtrace_top_level()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    let error = postscript_interpreter.interpret("1 2\n nosuchname").unwrap_err();
+    assert!(postscript_interpreter.error_report(&error).ends_with("Backtrace:\n   top level at -:2:2"));
+
+    let error = postscript_interpreter.interpret("{ 1").unwrap_err();
+    assert!(!postscript_interpreter.error_report(&error).contains("Backtrace"));
+}
+
+// Normal test case to ensure the stack listing puts the top first
+#[test]
+fn test_stack_listing()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("1 2.5 (three) /four true").unwrap();
+
+    assert_eq!(postscript_interpreter.stack_listing(), "true\n/four\n(three)\n2.5\n1");
+}
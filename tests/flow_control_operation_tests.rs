@@ -118,3 +118,55 @@ fn test_for_values()
 
 }
 
+
+// Edge test case to ensure repeat tells a bad count type from a bad value
+#[test]
+fn test_repeat_count_errors()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    assert_eq!(i.interpret("(3) { } repeat").unwrap_err(), "/typecheck in --repeat--");
+    assert_eq!(i.interpret("2.5 { } repeat").unwrap_err(), "/typecheck in --repeat--");
+    assert_eq!(i.interpret("-1 { } repeat").unwrap_err(), "/rangecheck in --repeat--");
+}
+
+// Edge test case to ensure a zero increment is allowed and counts upwards
+#[test]
+fn test_for_zero_increment()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    // initial is above limit, so the body never runs
+    i.interpret("3 0 1 { } for").unwrap();
+    assert_eq!(i.len(), 0);
+
+    // otherwise it runs until the body fails
+    assert_eq!(i.interpret("1 0 1 { (a) add } for").unwrap_err(), "/typecheck in --add--");
+}
+
+// Edge test case to ensure the control variable stops at the largest integer
+#[test]
+fn test_for_integer_limit()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("2147483646 1 2147483647 { } for").unwrap();
+    assert_eq!(i.len(), 2);
+}
+
+// Normal test case to ensure exec runs procedures and executable names
+// and pushes anything else back
+#[test]
+fn test_exec()
+{
+    let mut i = Interpreter::new(ScopeMode::Dynamic);
+
+    i.interpret("{ 1 2 add } exec 5 exec /x exec").unwrap();
+    assert_eq!(i.stack_listing(), "/x\n5\n3");
+
+    // A name bound to an executable name runs it
+    i.interpret("clear /plus (add) token pop exch pop def 4 5 plus").unwrap();
+    assert_eq!(i.stack_listing(), "9");
+
+    assert_eq!(i.interpret("(nosuchname) token pop exch pop exec").unwrap_err().name, "undefined");
+}
@@ -0,0 +1,124 @@
+// -----------------------------------------------------------------------------
+// File: log_tests.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Unit tests for the diagnostics log, its levels and the execution trace.
+// -----------------------------------------------------------------------------
+
+use ps_interpreter::{Interpreter, ScopeMode};
+use ps_interpreter::interpreter::log::LogLevel;
+
+// Normal test case to ensure definitions and the dictionary stack are
+// logged at debug level, without procedure calls
+#[test]
+fn test_debug_level()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.host.log.set_level(Some(LogLevel::Debug));
+    let log = postscript_interpreter.host.log.capture();
+
+    postscript_interpreter.interpret("/f { 5 dict begin /x (a) def end } def f").unwrap();
+
+    assert_eq!(log.text(), "[debug] def /f {5 dict begin /x (a) def end}\n\
+                            [debug] begin: dictionary stack depth 3\n\
+                            [debug] def /x (a)\n\
+                            [debug] end: dictionary stack depth 2\n");
+}
+
+// Normal test case to ensure trace level adds procedure calls, including
+// ones that fail
+#[test]
+fn test_trace_level()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+    postscript_interpreter.host.log.set_level(Some(LogLevel::Trace));
+    postscript_interpreter.interpret("/g { pop } def /f { g } def").unwrap();
+    let log = postscript_interpreter.host.log.capture();
+
+    postscript_interpreter.interpret("1 f").unwrap();
+    assert_eq!(log.take(), "[trace] call f (lexical)\n[trace] call g (lexical)\n[trace] return from g\n[trace] return from f\n");
+
+    assert!(postscript_interpreter.interpret("f").is_err());
+    assert_eq!(log.take(), "[trace] call f (lexical)\n\
+                            [trace] call g (lexical)\n\
+                            [trace] unwind g: /stackunderflow in --pop--\n\
+                            [trace] unwind f: /stackunderflow in --pop--\n");
+}
+
+// Normal test case to ensure warn level only reports likely mistakes
+#[test]
+fn test_warn_level()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.host.log.set_level(Some(LogLevel::Warn));
+    let log = postscript_interpreter.host.log.capture();
+
+    postscript_interpreter.interpret("/x 1 def /add { sub } def").unwrap();
+
+    assert_eq!(log.text(), "[warn] def /add is hidden by the builtin operator add\n");
+}
+
+// Edge test case to ensure nothing is logged while the log is off
+#[test]
+fn test_log_off()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    let log = postscript_interpreter.host.log.capture();
+
+    postscript_interpreter.interpret("/x 1 def 1 dict begin end").unwrap();
+
+    assert!(!postscript_interpreter.host.log.enabled(LogLevel::Warn));
+    assert_eq!(log.text(), "");
+}
+
+// Normal test case to ensure level names round trip
+#[test]
+fn test_level_names()
+{
+    for level in [LogLevel::Warn, LogLevel::Debug, LogLevel::Trace]
+    {
+        assert_eq!(LogLevel::from_name(level.name()), Some(level));
+    }
+
+    assert_eq!(LogLevel::from_name("loud"), None);
+}
+
+// Normal test case to ensure the execution trace shows every token with
+// its depth and the top of the stack before and after
+#[test]
+fn test_exec_trace()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.interpret("/f { 2 mul } def 1 2 3").unwrap();
+    let log = postscript_interpreter.host.log.capture();
+
+    postscript_interpreter.interpret("true settrace 4 f false settrace 5").unwrap();
+
+    assert_eq!(log.text(), "[exec] 0 4  [1 2 3]\n\
+                            [exec] 0 4  -> [.. 2 3 4]\n\
+                            [exec] 0 f  [.. 2 3 4]\n\
+                            [exec]   1 2  [.. 2 3 4]\n\
+                            [exec]   1 2  -> [.. 3 4 2]\n\
+                            [exec]   1 mul  [.. 3 4 2]\n\
+                            [exec]   1 mul  -> [.. 2 3 8]\n\
+                            [exec] 0 f  -> [.. 2 3 8]\n\
+                            [exec] 0 false  [.. 2 3 8]\n\
+                            [exec] 0 false  -> [.. 3 8 false]\n\
+                            [exec] 0 settrace  [.. 3 8 false]\n\
+                            [exec] 0 settrace  -> [.. 2 3 8]\n");
+}
+
+// Error test case to ensure a failing token is traced with its error
+#[test]
+fn test_exec_trace_error()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.host.exec_trace = true;
+    let log = postscript_interpreter.host.log.capture();
+
+    assert!(postscript_interpreter.interpret("(a) 1 add").is_err());
+    assert!(log.text().ends_with("[exec] 0 add  [(a) 1]\n[exec] 0 add  -> /typecheck in --add--\n"));
+
+    assert_eq!(postscript_interpreter.interpret("1 settrace").unwrap_err(), "/typecheck in --settrace--");
+}
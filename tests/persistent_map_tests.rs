@@ -0,0 +1,95 @@
+// -----------------------------------------------------------------------------
+// File: persistent_map_tests.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Unit tests for the persistent map behind dictionaries.
+// -----------------------------------------------------------------------------
+
+use ps_interpreter::{Interpreter, ScopeMode};
+use ps_interpreter::interpreter::pmap::PersistentMap;
+use ps_interpreter::interpreter::value::Value;
+
+// Normal test case to ensure inserted keys can be read back
+#[test]
+fn test_insert_and_get()
+{
+    let mut map = PersistentMap::new();
+
+    for i in 0..1000
+    {
+        map.insert(format!("key{}", i), i);
+    }
+
+    assert_eq!(map.len(), 1000);
+    for i in 0..1000
+    {
+        assert_eq!(map.get(&format!("key{}", i)), Some(&i));
+    }
+    assert_eq!(map.get("missing"), None);
+}
+
+// Normal test case to ensure inserting an existing key replaces it
+#[test]
+fn test_overwrite()
+{
+    let mut map = PersistentMap::new();
+
+    map.insert("x".to_string(), 1);
+    map.insert("x".to_string(), 2);
+
+    assert_eq!(map.len(), 1);
+    assert_eq!(map.get("x"), Some(&2));
+}
+
+// Normal test case to ensure iter visits every entry exactly once
+#[test]
+fn test_iter()
+{
+    let mut map = PersistentMap::new();
+
+    for i in 0..200
+    {
+        map.insert(i.to_string(), i);
+    }
+
+    let mut seen: Vec<i32> = map.iter().map(|(_, v)| *v).collect();
+    seen.sort();
+    assert_eq!(seen, (0..200).collect::<Vec<i32>>());
+}
+
+// Edge test case to ensure a clone does not see later changes
+#[test]
+fn test_clone_is_independent()
+{
+    let mut map = PersistentMap::new();
+    map.insert("a".to_string(), 1);
+
+    let copy = map.clone();
+    map.insert("a".to_string(), 10);
+    map.insert("b".to_string(), 2);
+
+    assert_eq!(copy.len(), 1);
+    assert_eq!(copy.get("a"), Some(&1));
+    assert_eq!(copy.get("b"), None);
+    assert_eq!(map.get("a"), Some(&10));
+}
+
+// Normal test case to ensure a lexical capture still freezes a large dictionary
+#[test]
+fn test_capture_large_dictionary()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    for i in 0..500
+    {
+        postscript_interpreter.interpret(&format!("/v{} {} def", i, i)).unwrap();
+    }
+
+    postscript_interpreter.interpret("/f { v250 } def /v250 0 def f").unwrap();
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(250) => {}
+        _ => panic!("expected the captured value 250"),
+    }
+}
@@ -0,0 +1,120 @@
+// -----------------------------------------------------------------------------
+// File: profile_tests.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Unit tests for the profiler's counts, report and collapsed stacks.
+// -----------------------------------------------------------------------------
+
+use ps_interpreter::{Interpreter, ScopeMode};
+use ps_interpreter::interpreter::profile::Profiler;
+
+// An interpreter that profiles everything it runs.
+fn profiling() -> Interpreter
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.host.profiler = Some(Profiler::new());
+    postscript_interpreter
+}
+
+// Normal test case to ensure operators and procedures are counted
+#[test]
+fn test_counts()
+{
+    let mut postscript_interpreter = profiling();
+
+    postscript_interpreter.interpret("/sq { dup mul } def /f { 0 1 1 5 { sq add } for } def f").unwrap();
+
+    let profiler = postscript_interpreter.host.profiler.as_ref().unwrap();
+    assert_eq!(profiler.stats("f").unwrap().calls, 1);
+    assert_eq!(profiler.stats("sq").unwrap().calls, 5);
+    assert_eq!(profiler.stats("--for--").unwrap().calls, 1);
+    assert_eq!(profiler.stats("--mul--").unwrap().calls, 5);
+    assert_eq!(profiler.stats("--def--").unwrap().calls, 2);
+
+    // Names that are not operators are not counted as operators.
+    assert_eq!(profiler.stats("--f--"), None);
+    assert_eq!(profiler.stats("--sq--"), None);
+}
+
+// Normal test case to ensure the time of an outer call includes its
+// inner calls, and own time does not
+#[test]
+fn test_times()
+{
+    let mut postscript_interpreter = profiling();
+
+    postscript_interpreter.interpret("/f { 1 1 1000 { pop } for } def f").unwrap();
+
+    let profiler = postscript_interpreter.host.profiler.as_ref().unwrap();
+    let f = profiler.stats("f").unwrap();
+    let for_loop = profiler.stats("--for--").unwrap();
+
+    assert!(f.total >= for_loop.total);
+    assert!(for_loop.own <= for_loop.total);
+    assert!(f.own <= f.total - for_loop.total + std::time::Duration::from_micros(1));
+}
+
+// Edge test case to ensure recursive calls count every call but only the
+// outermost one's time
+#[test]
+fn test_recursion()
+{
+    let mut postscript_interpreter = profiling();
+
+    postscript_interpreter.interpret("/down { dup 0 gt { 1 sub down } if } def 10 down").unwrap();
+
+    let profiler = postscript_interpreter.host.profiler.as_ref().unwrap();
+    let down = profiler.stats("down").unwrap();
+    assert_eq!(down.calls, 11);
+    assert!(down.own <= down.total);
+}
+
+// Normal test case to ensure the report lists the most expensive first
+#[test]
+fn test_report()
+{
+    let mut postscript_interpreter = profiling();
+
+    postscript_interpreter.interpret("/f { 1 1 100 { pop } for } def f").unwrap();
+
+    let report = postscript_interpreter.host.profiler.as_ref().unwrap().report();
+    let lines: Vec<&str> = report.lines().collect();
+
+    assert!(lines[0].ends_with("calls     total ms       own ms  name"));
+    assert!(lines[1].ends_with("  f"));
+    assert!(lines[2].ends_with("  --for--"));
+    assert_eq!(lines.len(), 5);
+}
+
+// Normal test case to ensure collapsed stacks name every frame from the
+// outermost in
+#[test]
+fn test_collapsed_stacks()
+{
+    let mut postscript_interpreter = profiling();
+
+    postscript_interpreter.interpret("/g { 1 pop } def /f { true { g } if } def f").unwrap();
+
+    let stacks: Vec<String> = postscript_interpreter.host.profiler.as_ref().unwrap()
+        .collapsed_stacks()
+        .lines()
+        .map(|line| line.rsplit_once(' ').unwrap().0.to_string())
+        .collect();
+
+    assert_eq!(stacks, ["--def--", "f", "f;--if--", "f;--if--;g", "f;--if--;g;--pop--"]);
+}
+
+// Error test case to ensure a failing operator still closes its frame
+#[test]
+fn test_profile_error()
+{
+    let mut postscript_interpreter = profiling();
+
+    assert!(postscript_interpreter.interpret("/f { pop } def f").is_err());
+    postscript_interpreter.interpret("1 pop").unwrap();
+
+    let profiler = postscript_interpreter.host.profiler.as_ref().unwrap();
+    assert_eq!(profiler.stats("--pop--").unwrap().calls, 2);
+    assert!(profiler.collapsed_stacks().lines().any(|line| line.starts_with("--pop-- ")));
+}
@@ -0,0 +1,405 @@
+// -----------------------------------------------------------------------------
+// File: repl_tests.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Unit tests for the REPL helpers.
+// -----------------------------------------------------------------------------
+
+use ps_interpreter::{Interpreter, ScopeMode};
+use ps_interpreter::interpreter::builtin::OPERATORS;
+use ps_interpreter::interpreter::value::Value;
+use ps_interpreter::interpreter::profile::Profiler;
+use ps_interpreter::repl::commands::meta_command;
+use ps_interpreter::repl::{completions, ReplOptions};
+use ps_interpreter::repl::rollback::Checkpoint;
+use ps_interpreter::repl::editor::{Completion, History, LineBuffer};
+use ps_interpreter::repl::terminal::{read_key, Key};
+use ps_interpreter::repl::input::needs_more_input;
+
+// Normal test case to ensure complete input is run right away
+#[test]
+fn test_complete_input()
+{
+    assert!(!needs_more_input("1 2 add\n"));
+    assert!(!needs_more_input("/f { 1 { 2 } if } def\n"));
+    assert!(!needs_more_input("(a (nested) string) <48 49>\n"));
+    assert!(!needs_more_input("<< /a 1 >>\n"));
+}
+
+// Normal test case to ensure open delimiters wait for more lines
+#[test]
+fn test_open_delimiters()
+{
+    assert!(needs_more_input("/f {\n"));
+    assert!(needs_more_input("/f { { 1 }\n"));
+    assert!(needs_more_input("(an open string\n"));
+    assert!(needs_more_input("<48 49\n"));
+    assert!(needs_more_input("<< /a 1\n"));
+}
+
+// Edge test case to ensure delimiters inside strings are ignored
+#[test]
+fn test_delimiters_in_strings()
+{
+    assert!(!needs_more_input("({) =\n"));
+    assert!(!needs_more_input("(\\)) =\n"));
+    assert!(needs_more_input("{ (}) \n"));
+}
+
+// Edge test case to ensure delimiters inside comments are ignored, but
+// a comment character inside a string is not a comment
+#[test]
+fn test_delimiters_in_comments()
+{
+    assert!(!needs_more_input("1 2 add % a { comment\n"));
+    assert!(!needs_more_input("% don't (\n"));
+    assert!(needs_more_input("/f { % }\n"));
+    assert!(needs_more_input("(100% {\n"));
+}
+
+// Edge test case to ensure stray closers do not wait forever
+#[test]
+fn test_stray_closers()
+{
+    assert!(!needs_more_input("}\n"));
+    assert!(!needs_more_input("} {\n"));
+    assert!(!needs_more_input(">>\n"));
+}
+
+// Normal test case to ensure :stack lists the operands top first
+#[test]
+fn test_stack_command()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":stack"), "(empty stack)");
+
+    postscript_interpreter.interpret("1 (two) /three").unwrap();
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":stack"), "/three\n(two)\n1");
+}
+
+// Normal test case to ensure :dicts shows every dictionary and its names
+#[test]
+fn test_dicts_command()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/b 2 def /a 1 def 1 dict begin /c 3 def").unwrap();
+
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":dicts"),
+        "2 dict (1 entries) c\n1 userdict (2 entries) a b\n0 globaldict (0 entries)");
+}
+
+// Normal test case to ensure :scope shows and changes the scoping mode
+#[test]
+fn test_scope_command()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":scope"), "dynamic");
+    meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":scope lexical");
+    assert_eq!(postscript_interpreter.scope_mode, ScopeMode::Lexical);
+
+    assert!(meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":scope static").starts_with("Error"));
+}
+
+// Normal test case to ensure :load runs a file
+#[test]
+fn test_load_command()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    let path = std::env::temp_dir().join(format!("ps_interpreter_load_{}.ps", std::process::id()));
+    std::fs::write(&path, "/loaded 7 def").unwrap();
+
+    meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), &format!(":load {}", path.display()));
+    postscript_interpreter.interpret("loaded").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(7) => {}
+        _ => panic!("expected the loaded definition"),
+    }
+
+    assert!(meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":load /no/such/file.ps").starts_with("Error"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+// Normal test case to ensure :reset forgets everything but the settings
+#[test]
+fn test_reset_command()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+    let output = postscript_interpreter.capture_output();
+    postscript_interpreter.host.exec_trace = true;
+    postscript_interpreter.host.profiler = Some(Profiler::new());
+
+    postscript_interpreter.interpret("/x 1 def 1 2 3").unwrap();
+    meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":reset");
+
+    assert!(postscript_interpreter.is_empty());
+    assert!(postscript_interpreter.interpret("x").is_err());
+    assert_eq!(postscript_interpreter.scope_mode, ScopeMode::Lexical);
+    assert!(postscript_interpreter.host.exec_trace);
+
+    // The profiler is kept along with what it counted before the reset
+    let profiler = postscript_interpreter.host.profiler.as_ref().expect("the profiler should survive :reset");
+    assert_eq!(profiler.stats("--def--").unwrap().calls, 1);
+
+    postscript_interpreter.interpret("(still captured) print").unwrap();
+    assert_eq!(output.text(), "still captured");
+}
+
+// Normal test case to ensure :time runs the code and reports a time
+#[test]
+fn test_time_command()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let output = meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":time 0 0 1 1000 { add } for");
+    assert!(output.starts_with("time: "));
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(500500) => {}
+        _ => panic!("expected the sum 500500"),
+    }
+}
+
+// Normal test case to ensure :help describes operators and names
+#[test]
+fn test_help_command()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    assert!(meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":help").contains(":load file.ps"));
+    assert!(meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":help add").starts_with("num1 num2 add sum"));
+
+    postscript_interpreter.interpret("/x 5 def").unwrap();
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut ReplOptions::default(), ":help x"), "x is defined as 5");
+}
+
+// Edge test case to ensure every operator listed for :help really exists
+#[test]
+fn test_operator_table_matches_builtins()
+{
+    for (name, _, _) in OPERATORS
+    {
+        let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+        if let Err(e) = postscript_interpreter.interpret(name)
+        {
+            assert_ne!(e.name, "undefined", "{} is listed but not a builtin", name);
+        }
+    }
+}
+
+// Normal test case to ensure cursor movement and editing keys work
+#[test]
+fn test_line_buffer_editing()
+{
+    let mut line = LineBuffer::new();
+
+    for c in "1 2 ad".chars()
+    {
+        line.insert(c);
+    }
+    line.insert('d');
+    assert_eq!(line.text(), "1 2 add");
+
+    line.home();
+    line.delete();
+    line.insert('3');
+    assert_eq!(line.text(), "3 2 add");
+
+    line.end();
+    line.left();
+    line.backspace();
+    assert_eq!(line.text(), "3 2 ad");
+
+    line.kill_to_start();
+    assert_eq!(line.text(), "d");
+    assert_eq!(line.cursor(), 0);
+    line.kill_to_end();
+    assert_eq!(line.text(), "");
+}
+
+// Normal test case to ensure tab completion extends or lists names
+#[test]
+fn test_line_buffer_completion()
+{
+    let names: Vec<String> = ["def", "dict", "dup", "define_me"].iter().map(|s| s.to_string()).collect();
+    let mut line = LineBuffer::new();
+
+    // A single match is finished with a space
+    line.set("/x 1 du");
+    assert_eq!(line.complete(&names), Completion::Extended);
+    assert_eq!(line.text(), "/x 1 dup ");
+
+    // Several matches extend as far as they agree
+    line.set("/x de");
+    assert_eq!(line.complete(&names), Completion::Extended);
+    assert_eq!(line.text(), "/x def");
+
+    // And are listed once they differ at the cursor
+    line.set("d");
+    assert_eq!(line.complete(&names), Completion::Choices(vec!["def".to_string(), "define_me".to_string(), "dict".to_string(), "dup".to_string()]));
+
+    // The / of a literal name is not part of the word
+    line.set("/di");
+    line.complete(&names);
+    assert_eq!(line.text(), "/dict ");
+
+    line.set("zz");
+    assert_eq!(line.complete(&names), Completion::NoMatch);
+}
+
+// Normal test case to ensure history is saved and loaded again
+#[test]
+fn test_history_file()
+{
+    let path = std::env::temp_dir().join(format!("ps_interpreter_history_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut history = History::load(Some(path.clone()));
+    history.add("1 2 add");
+    history.add("1 2 add");
+    history.add("   ");
+    history.add("/x 1 def");
+
+    let reloaded = History::load(Some(path.clone()));
+    assert_eq!(reloaded.entries(), ["1 2 add".to_string(), "/x 1 def".to_string()]);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+// Normal test case to ensure key presses are decoded from terminal bytes
+#[test]
+fn test_read_key()
+{
+    let mut input: &[u8] = b"a\r\t\x7f\x1b[A\x1b[3~\x03\xc3\xa9";
+
+    let expected = [Key::Char('a'), Key::Enter, Key::Tab, Key::Backspace, Key::Up, Key::Delete, Key::Ctrl('c'), Key::Char('\u{e9}')];
+    for key in expected
+    {
+        assert_eq!(read_key(&mut input).unwrap(), Some(key));
+    }
+    assert_eq!(read_key(&mut input).unwrap(), None);
+}
+
+// Normal test case to ensure completion offers builtins, defined names and commands
+#[test]
+fn test_completions()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.interpret("/counter 0 def").unwrap();
+
+    let mut names = completions(&postscript_interpreter, "cou");
+    names.sort();
+    assert_eq!(names, ["count", "counter"]);
+
+    assert_eq!(completions(&postscript_interpreter, ":he"), [":help"]);
+}
+
+// Normal test case to ensure a failed line is undone and described
+#[test]
+fn test_rollback_failed_line()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.interpret("/x 1 def 7").unwrap();
+
+    let checkpoint = Checkpoint::take(&mut postscript_interpreter);
+    let result = postscript_interpreter.interpret("/x 2 def /y 3 def 1 dict begin 8 9 undefined_name");
+    assert!(result.is_err());
+
+    let report = checkpoint.restore(&mut postscript_interpreter);
+    assert_eq!(report, "Rolled back: operand stack back to 1 entries (was 3); \
+        dictionary stack back to 2 dictionaries (was 3); undid definitions of x y");
+
+    // Everything is as it was before the line
+    postscript_interpreter.interpret("x").unwrap();
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Int(7), Value::Int(1)] => {}
+        _ => panic!("expected the stack from before the failed line"),
+    }
+    assert!(postscript_interpreter.interpret("y").is_err());
+}
+
+// Edge test case to ensure popped operands come back
+#[test]
+fn test_rollback_restores_popped_operands()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.interpret("1 2").unwrap();
+
+    let checkpoint = Checkpoint::take(&mut postscript_interpreter);
+    assert!(postscript_interpreter.interpret("pop pop pop").is_err());
+    checkpoint.restore(&mut postscript_interpreter);
+
+    assert_eq!(postscript_interpreter.len(), 2);
+}
+
+// Normal test case to ensure rollback also undoes changes made through
+// objects, saves and the allocation mode
+#[test]
+fn test_rollback_objects_and_vm()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.interpret("/s (abc) def /d 1 dict def").unwrap();
+
+    let checkpoint = Checkpoint::take(&mut postscript_interpreter);
+    let result = postscript_interpreter.interpret("s 0 88 put d /k 1 put true setglobal save pop undefined_name");
+    assert!(result.is_err());
+
+    let report = checkpoint.restore(&mut postscript_interpreter);
+    assert_eq!(report, "Rolled back: undid changes to 2 other dictionaries and strings");
+
+    postscript_interpreter.interpret("s d length currentglobal").unwrap();
+    assert_eq!(postscript_interpreter.stack_listing(), "false\n0\n(abc)");
+    assert_eq!(postscript_interpreter.vm.level(), 0);
+}
+
+// Edge test case to ensure a line that succeeds keeps its changes, and
+// saves it made stay usable
+#[test]
+fn test_rollback_discard()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    postscript_interpreter.interpret("/x 1 def").unwrap();
+
+    let checkpoint = Checkpoint::take(&mut postscript_interpreter);
+    postscript_interpreter.interpret("/x 2 def save /x 3 def").unwrap();
+    checkpoint.discard(&mut postscript_interpreter);
+    assert_eq!(postscript_interpreter.vm.level(), 1);
+
+    postscript_interpreter.interpret("restore x").unwrap();
+    assert_eq!(postscript_interpreter.stack_listing(), "2");
+}
+
+// Normal test case to ensure :rollback switches the option
+#[test]
+fn test_rollback_command()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    let mut options = ReplOptions::default();
+
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut options, ":rollback"), "rollback is off");
+    meta_command(&mut postscript_interpreter, &mut options, ":rollback on");
+    assert!(options.rollback);
+    assert!(meta_command(&mut postscript_interpreter, &mut options, ":rollback maybe").starts_with("Error"));
+}
+
+// Normal test case to ensure :trace switches the execution trace
+#[test]
+fn test_trace_command()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+    let mut options = ReplOptions::default();
+
+    assert_eq!(meta_command(&mut postscript_interpreter, &mut options, ":trace"), "trace is off");
+    meta_command(&mut postscript_interpreter, &mut options, ":trace on");
+    assert!(postscript_interpreter.host.exec_trace);
+    assert!(meta_command(&mut postscript_interpreter, &mut options, ":trace loud").starts_with("Error"));
+}
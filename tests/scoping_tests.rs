@@ -7,6 +7,8 @@
 // be changed by the user.
 // -----------------------------------------------------------------------------
 
+use std::rc::Rc;
+
 use ps_interpreter::{Interpreter, ScopeMode};
 use ps_interpreter::interpreter::value::Value;
 
@@ -76,3 +78,337 @@ fn test_lexical_scope_nested() {
         _ => panic!("Lexical scoping failed: nested lookup should use original 'a' = 100"),
     }
 }
+
+// Normal test case to ensure a procedure nested in another procedure
+// captures the environment the outer procedure runs in.
+#[test]
+fn test_lexical_scope_nested_closure()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    // outer returns the inner procedure { x } without running it
+    postscript_interpreter.interpret("/x 1 def /outer { { x } } def").unwrap();
+
+    // Rebind x, then run the returned procedure through if
+    postscript_interpreter.interpret("/x 2 def outer true exch if").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(1) => {},
+        _ => panic!("Lexical scoping failed: nested closure should see x = 1"),
+    }
+}
+
+// Normal test case to ensure a procedure passed as an argument keeps the
+// bindings from where it was written, not from the procedure calling it.
+#[test]
+fn test_lexical_scope_higher_order()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    // apply rebinds n and then runs the procedure it was given
+    postscript_interpreter.interpret("/n 10 def /apply { /n 99 def 1 exch repeat } def").unwrap();
+
+    // The procedure literal is captured while n is still 10
+    postscript_interpreter.interpret("{ n } apply").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(10) => {},
+        _ => panic!("Lexical scoping failed: argument procedure should see n = 10"),
+    }
+}
+
+// Normal test case to ensure procedures given to control operators at
+// the top level are captured when they are pushed.
+#[test]
+fn test_lexical_scope_control_operator_capture()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    // The procedure is pushed while y is 5, then y changes before for runs it
+    postscript_interpreter.interpret("/y 5 def 1 1 2 { pop y } /y 6 def for").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Int(5), Value::Int(5)] => {},
+        _ => panic!("Lexical scoping failed: for body should see y = 5"),
+    }
+}
+
+// Normal test case to ensure a lexical procedure sees its own definitions.
+#[test]
+fn test_lexical_def_visible_in_body()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/f { /x 1 def x } def f").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(1) => {},
+        _ => panic!("Lexical scoping failed: body should see its own x = 1"),
+    }
+}
+
+// Normal test case to ensure definitions made by a call stay local to it.
+#[test]
+fn test_lexical_def_is_local()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/x 5 def /f { /x 1 def /y 2 def } def f x").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(5) => {},
+        _ => panic!("Lexical scoping failed: call should not change the top level x"),
+    }
+
+    let result = postscript_interpreter.interpret("y");
+    assert!(result.is_err(), "y was defined inside f and should not exist afterwards");
+}
+
+// Normal test case to ensure begin and end work inside a lexical procedure.
+#[test]
+fn test_lexical_begin_end_in_body()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/f { 1 dict begin /y 2 def y end } def f").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(2) => {},
+        _ => panic!("Lexical scoping failed: expected y = 2 from the begun dictionary"),
+    }
+    assert_eq!(postscript_interpreter.dict.env().borrow().len(), 2);
+}
+
+// Error test case to ensure end can not remove a call's local dictionary.
+#[test]
+fn test_lexical_end_below_activation()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/f { end } def").unwrap();
+
+    let result = postscript_interpreter.interpret("f");
+    assert!(result.is_err(), "end should not pop the local dictionary of f");
+}
+
+// Normal test case to ensure a control operator body defines names in
+// the call it belongs to.
+#[test]
+fn test_lexical_def_in_control_body()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/f { true { /z 3 def } if z } def f").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(3) => {},
+        _ => panic!("Lexical scoping failed: expected z = 3 defined by the if body"),
+    }
+
+    let result = postscript_interpreter.interpret("z");
+    assert!(result.is_err(), "z belongs to the call of f");
+}
+
+// Normal test case to ensure a control operator body at the top level
+// defines names in the live dictionary stack.
+#[test]
+fn test_lexical_def_in_top_level_control_body()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("true { /z 4 def } if z").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(4) => {},
+        _ => panic!("Lexical scoping failed: expected top level z = 4"),
+    }
+}
+
+// Normal test case to ensure a lexical procedure can call itself.
+#[test]
+fn test_lexical_recursion()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/fact { dup 1 le { pop 1 } { dup 1 sub fact mul } ifelse } def 5 fact").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(120) => {},
+        _ => panic!("Lexical scoping failed: expected 5 fact = 120"),
+    }
+}
+
+// Normal test case to ensure closures made by separate calls keep
+// separate locals.
+#[test]
+fn test_lexical_closures_per_call()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/mk { /n exch def { n } } def 1 mk 2 mk true exch if exch true exch if").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Int(2), Value::Int(1)] => {},
+        _ => panic!("Lexical scoping failed: each closure should keep its own n"),
+    }
+}
+
+// Normal test case to ensure a call whose closures are defined in its
+// own local dictionary is freed once nothing else holds it, and that a
+// closure leaving the call still finds those definitions.
+#[test]
+fn test_lexical_local_closure_freed()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/f { 1 dict dup /d exch def /g { d } def } def f").unwrap();
+    let local = match postscript_interpreter.peek().unwrap()
+    {
+        Value::Dict(local) => Rc::downgrade(local),
+        _ => panic!("expected the dictionary defined in the call"),
+    };
+
+    postscript_interpreter.interpret("clear").unwrap();
+    assert!(local.upgrade().is_none(), "the activation should not own itself");
+
+    postscript_interpreter.interpret("/mk { /n 7 def /g { n } def { g } } def mk true exch if").unwrap();
+    assert_eq!(postscript_interpreter.stack_listing(), "7");
+}
+
+// Normal test case to ensure currentscoping reports the mode set by setscoping.
+#[test]
+fn test_setscoping_currentscoping()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("currentscoping /lexical setscoping currentscoping").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Name(before), Value::Name(after)] =>
+        {
+            assert_eq!(before, "dynamic");
+            assert_eq!(after, "lexical");
+        }
+        _ => panic!("expected two scoping mode names"),
+    }
+    assert_eq!(postscript_interpreter.scope_mode, ScopeMode::Lexical);
+}
+
+// Error test case to ensure setscoping rejects unknown modes.
+#[test]
+fn test_setscoping_invalid()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    assert!(postscript_interpreter.interpret("/static setscoping").is_err());
+    assert!(postscript_interpreter.interpret("1 setscoping").is_err());
+}
+
+// Normal test case to ensure a procedure keeps the mode it was defined in
+// after the interpreter switches modes.
+#[test]
+fn test_procedures_keep_their_scoping()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    // lex is defined lexically and sees x = 1
+    postscript_interpreter.interpret("/x 1 def /lex { x } def").unwrap();
+
+    // dyn is defined dynamically and sees whatever x is when it runs
+    postscript_interpreter.interpret("/dynamic setscoping /dyn { x } def").unwrap();
+
+    postscript_interpreter.interpret("/x 2 def lex dyn").unwrap();
+    postscript_interpreter.interpret("/lexical setscoping /x 3 def lex dyn").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Int(1), Value::Int(2), Value::Int(1), Value::Int(3)] => {},
+        _ => panic!("procedures should keep the scoping mode they were defined in"),
+    }
+}
+
+// Normal test case to ensure a procedure scanned by token in lexical mode
+// is lexical, while one made dynamic on purpose stays dynamic when defined
+#[test]
+fn test_def_keeps_scanned_and_dynamic_procedures_apart()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/x 1 def /scanned ({ x }) token pop exch pop def /dyn { x } dynamic def").unwrap();
+    postscript_interpreter.interpret("/x 2 def scanned dyn").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Int(1), Value::Int(2)] => {},
+        _ => panic!("scanned should see x = 1 and dyn should see x = 2"),
+    }
+}
+
+// Normal test case to ensure single procedures can be made lexical in a
+// dynamically scoped program.
+#[test]
+fn test_lexical_operator()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/v 1 def /getv { v } lexical def /cb { v } def /v 2 def getv cb").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Int(1), Value::Int(2)] => {},
+        _ => panic!("getv should see v = 1 and cb should see v = 2"),
+    }
+}
+
+// Normal test case to ensure dynamic library code can rebind a variable
+// its callback reads in a lexically scoped program.
+#[test]
+fn test_dynamic_operator()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/v 1 def /lib { /v 42 def cb } dynamic def /cb { v } dynamic def lib").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(42) => {},
+        _ => panic!("the dynamic callback should see the v set by lib"),
+    }
+}
+
+// Normal test case to ensure procedure literals inside a dynamic procedure
+// are dynamic too.
+#[test]
+fn test_dynamic_procedure_nested_literal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    postscript_interpreter.interpret("/v 5 def /mk { { v } } dynamic def mk /v 6 def true exch if").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(6) => {},
+        _ => panic!("the inner procedure should look v up when it runs"),
+    }
+}
+
+// Error test case to ensure lexical and dynamic only accept procedures.
+#[test]
+fn test_scoping_operators_typecheck()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Lexical);
+
+    assert!(postscript_interpreter.interpret("1 lexical").is_err());
+    assert!(postscript_interpreter.interpret("(x) dynamic").is_err());
+}
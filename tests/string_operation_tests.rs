@@ -100,7 +100,8 @@ fn test_putinterval_normal()
 {
     let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
 
-    postscript_interpreter.interpret("(hello) 1 (XYZ) putinterval").unwrap();
+    // putinterval consumes its operands, so keep a copy of the target
+    postscript_interpreter.interpret("(hello) dup 1 (XYZ) putinterval").unwrap();
 
     match postscript_interpreter.peek().unwrap()
     {
@@ -109,6 +110,86 @@ fn test_putinterval_normal()
     }
 }
 
+// Normal test case to ensure putinterval changes a string that
+// another variable also references
+#[test]
+fn test_putinterval_shared_string()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/a (hello) def /b a def a 0 (J) putinterval b").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Str(s) => assert_eq!(s, "Jello"),
+        _ => panic!("expected Jello"),
+    }
+}
+
+// Normal test case to ensure getinterval returns a view that shares
+// storage with the original string
+#[test]
+fn test_getinterval_shares_storage()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/s (hello) def s 1 3 getinterval 0 (ipp) putinterval s").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Str(s) => assert_eq!(s, "hippo"),
+        _ => panic!("expected hippo"),
+    }
+}
+
+// Normal test case to ensure binary data written with escapes
+// keeps every byte value
+#[test]
+fn test_binary_string_round_trip()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("(\\000\\377\\n) dup length exch 1 get").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    match (&stack[0], &stack[1])
+    {
+        (Value::Int(3), Value::Int(255)) => {}
+        _ => panic!("expected length 3 and byte 255"),
+    }
+}
+
+// Normal test case to ensure hex strings produce the bytes they spell
+#[test]
+fn test_hex_string_literal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("<68 65 6C 6c 6F>").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Str(s) => assert_eq!(s, "hello"),
+        _ => panic!("expected hello"),
+    }
+}
+
+// Normal test case to ensure comments are skipped up to the end of the
+// line, except inside string literals
+#[test]
+fn test_comments()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("%!PS\n1 2 add% three\n(50%) % a string\n").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Int(3), Value::Str(s)] => assert_eq!(s, "50%"),
+        _ => panic!("expected 3 and (50%)"),
+    }
+}
+
 // Edge test case to ensure out of range mutation
 // throws error
 #[test]
@@ -124,3 +205,214 @@ fn test_putinterval_range_error() {
 
 
 
+// Normal test case to ensure string allocates a buffer of zero bytes
+#[test]
+fn test_string_normal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("3 string dup length exch 0 get").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    match (&stack[0], &stack[1])
+    {
+        (Value::Int(3), Value::Int(0)) => {}
+        _ => panic!("expected a 3 byte string of zeros"),
+    }
+}
+
+// Edge test case to ensure a negative string size throws error
+#[test]
+fn test_string_negative_size()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("-1 string");
+
+    assert!(result.is_err(), "string should fail on a negative size");
+}
+
+// Edge test case to ensure string refuses sizes above the 65535 byte
+// limit instead of allocating them
+#[test]
+fn test_string_size_limit()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("2147483647 string");
+    assert_eq!(result.unwrap_err().name, "limitcheck");
+
+    let result = postscript_interpreter.interpret("clear 65536 string");
+    assert_eq!(result.unwrap_err().name, "limitcheck");
+
+    postscript_interpreter.interpret("clear 65535 string length").unwrap();
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(n) => assert_eq!(*n, 65535),
+        _ => panic!("expected a 65535 byte string"),
+    }
+}
+
+// Normal test case to ensure put writes a byte into a string
+#[test]
+fn test_put_string_normal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/s (cat) def s 0 98 put s").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Str(s) => assert_eq!(s, "bat"),
+        _ => panic!("expected bat"),
+    }
+}
+
+// Edge test case to ensure put rejects values that are not bytes
+#[test]
+fn test_put_string_range_error()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("(cat) 0 256 put");
+
+    assert!(result.is_err(), "put should fail on a value above 255");
+}
+
+// Normal test case to ensure search splits the string around the match
+#[test]
+fn test_search_found()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("(key=value) (=) search").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    match &stack[..]
+    {
+        [Value::Str(post), Value::Str(found), Value::Str(pre), Value::Bool(true)] =>
+        {
+            assert_eq!(post, "value");
+            assert_eq!(found, "=");
+            assert_eq!(pre, "key");
+        }
+        _ => panic!("expected post, match, pre and true"),
+    }
+}
+
+// Edge test case to ensure search pushes the string back when nothing matches
+#[test]
+fn test_search_not_found()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("(hello) (z) search").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    match &stack[..]
+    {
+        [Value::Str(s), Value::Bool(false)] => assert_eq!(s, "hello"),
+        _ => panic!("expected the original string and false"),
+    }
+}
+
+// Normal test case to ensure anchorsearch only matches at the start
+#[test]
+fn test_anchorsearch()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("(abcabc) (abc) anchorsearch (abcabc) (bc) anchorsearch").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    match &stack[..]
+    {
+        [Value::Str(post), Value::Str(found), Value::Bool(true), Value::Str(original), Value::Bool(false)] =>
+        {
+            assert_eq!(post, "abc");
+            assert_eq!(found, "abc");
+            assert_eq!(original, "abcabc");
+        }
+        _ => panic!("expected one anchored match and one miss"),
+    }
+}
+
+// Normal test case to ensure token scans objects out of a string
+#[test]
+fn test_token_normal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("( 12 {1 2 add} rest) token").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    match &stack[..]
+    {
+        [Value::Str(post), Value::Int(12), Value::Bool(true)] => assert_eq!(post, "{1 2 add} rest"),
+        _ => panic!("expected the rest of the string, 12 and true"),
+    }
+
+    // The procedure comes out as a single object
+    postscript_interpreter.interpret("pop pop token pop exch pop").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Procedure(procedure) => assert_eq!(procedure.body.len(), 3),
+        _ => panic!("expected a procedure"),
+    }
+}
+
+// Normal test case to ensure token scans the bytes of the string, so
+// bytes that are not valid text do not move where the rest starts
+#[test]
+fn test_token_raw_bytes()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("(\\(\\377\\) rest) token").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    match &stack[..]
+    {
+        [Value::Str(post), Value::Str(scanned), Value::Bool(true)] =>
+        {
+            assert_eq!(post, "rest");
+            assert_eq!(scanned.to_bytes(), [0xff]);
+        }
+        _ => panic!("expected the rest of the string, the scanned string and true"),
+    }
+}
+
+// Normal test case to ensure token returns names as executable names,
+// which exec runs
+#[test]
+fn test_token_executable_name()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("(add) token pop exch pop").unwrap();
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::ExecName(name) => assert_eq!(name, "add"),
+        _ => panic!("expected an executable name"),
+    }
+
+    postscript_interpreter.interpret("clear 1 2 (add) token pop exch pop exec").unwrap();
+    assert_eq!(postscript_interpreter.stack_listing(), "3");
+}
+
+// Edge test case to ensure token on blank input pushes only false
+#[test]
+fn test_token_empty()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("(   ) token").unwrap();
+
+    let stack = postscript_interpreter.opstack_snapshot();
+    match &stack[..]
+    {
+        [Value::Bool(false)] => {}
+        _ => panic!("expected false"),
+    }
+}
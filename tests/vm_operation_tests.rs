@@ -0,0 +1,270 @@
+// -----------------------------------------------------------------------------
+// File: vm_operation_tests.rs
+// Author: Quinn Bankhead
+// Project: PostScript Interpreter (CptS 355 - Mini Project)
+// Description:
+// Unit tests for the save and restore virtual memory operations.
+// -----------------------------------------------------------------------------
+
+use ps_interpreter::{Interpreter, ScopeMode};
+use ps_interpreter::interpreter::value::Value;
+
+// Normal test case to ensure restore rolls back definitions
+#[test]
+fn test_restore_definitions()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/x 1 def save /x 2 def /y 3 def restore").unwrap();
+
+    postscript_interpreter.interpret("x").unwrap();
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(1) => {}
+        _ => panic!("expected x to be rolled back to 1"),
+    }
+
+    let result = postscript_interpreter.interpret("y");
+    assert!(result.is_err(), "y was defined after the save and should be gone");
+}
+
+// Normal test case to ensure restore rolls back string contents
+#[test]
+fn test_restore_string_contents()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/s (hello) def save s 0 (J) putinterval restore s").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Str(s) => assert_eq!(s, "hello"),
+        _ => panic!("expected hello"),
+    }
+}
+
+// Normal test case to ensure restore rolls back dictionary stack changes
+#[test]
+fn test_restore_dictionary_stack()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/d 5 dict def save d begin restore").unwrap();
+
+    // Only globaldict and userdict are left
+    assert_eq!(postscript_interpreter.dict.env().borrow().len(), 2);
+}
+
+// Normal test case to ensure nested saves restore independently
+#[test]
+fn test_nested_save()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/x 1 def save /x 2 def save /x 3 def restore x exch restore x").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Int(2), Value::Int(1)] => {}
+        _ => panic!("expected 2 then 1"),
+    }
+}
+
+// Edge test case to ensure objects made after the save can not be
+// left on the operand stack
+#[test]
+fn test_restore_newer_object_on_stack()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("save 5 string exch restore");
+
+    assert_eq!(result.unwrap_err().name, "invalidrestore");
+}
+
+// Edge test case to ensure a procedure holding a string made after the
+// save can not be left on the operand stack either
+#[test]
+fn test_restore_newer_object_in_procedure()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    // The procedure is scanned after the save, so its string is newer.
+    postscript_interpreter.interpret("save").unwrap();
+    let result = postscript_interpreter.interpret("{(new)} exch restore");
+    assert_eq!(result.unwrap_err().name, "invalidrestore");
+
+    postscript_interpreter.interpret("clear save").unwrap();
+    let result = postscript_interpreter.interpret("{ 1 { (nested) } } exch restore");
+    assert_eq!(result.unwrap_err().name, "invalidrestore");
+
+    // Procedures of older objects only are fine
+    postscript_interpreter.interpret("clear { (old) } save restore").unwrap();
+}
+
+// Edge test case to ensure a save object can only be restored once
+#[test]
+fn test_restore_twice()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("save dup restore restore");
+
+    assert_eq!(result.unwrap_err().name, "invalidrestore");
+}
+
+// Edge test case to ensure restore needs a save object
+#[test]
+fn test_restore_type_error()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("5 restore");
+
+    assert_eq!(result.unwrap_err().name, "typecheck");
+}
+
+// Normal test case to ensure the allocation mode can be read and changed
+#[test]
+fn test_setglobal_currentglobal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("currentglobal true setglobal currentglobal false setglobal currentglobal").unwrap();
+
+    match &postscript_interpreter.opstack_snapshot()[..]
+    {
+        [Value::Bool(false), Value::Bool(true), Value::Bool(false)] => {}
+        _ => panic!("expected false true false"),
+    }
+}
+
+// Normal test case to ensure globaldict definitions survive restore
+#[test]
+fn test_globaldict_survives_restore()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("save globaldict /shared 7 put /local 8 def restore").unwrap();
+
+    postscript_interpreter.interpret("shared").unwrap();
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Int(7) => {}
+        _ => panic!("expected shared to still be 7"),
+    }
+
+    let result = postscript_interpreter.interpret("local");
+    assert!(result.is_err(), "local definitions should be rolled back");
+}
+
+// Normal test case to ensure strings made in global VM keep their
+// contents across restore and may be stored in globaldict
+#[test]
+fn test_global_string_survives_restore()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("true setglobal globaldict /buf 3 string put false setglobal").unwrap();
+    postscript_interpreter.interpret("save buf 0 (abc) putinterval restore buf").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Str(s) => assert_eq!(s, "abc"),
+        _ => panic!("expected abc"),
+    }
+}
+
+// Normal test case to ensure restore puts the allocation mode back
+#[test]
+fn test_restore_allocation_mode()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("save true setglobal restore currentglobal").unwrap();
+
+    match postscript_interpreter.peek().unwrap()
+    {
+        Value::Bool(false) => {}
+        _ => panic!("expected local allocation mode"),
+    }
+}
+
+// Edge test case to ensure local objects can not be stored in global ones
+#[test]
+fn test_local_into_global_invalidaccess()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("globaldict /s 3 string put");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+
+    let result = postscript_interpreter.interpret("globaldict begin /d 1 dict def");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+
+    // Simple values are fine
+    postscript_interpreter.interpret("clear /n 1 def end").unwrap();
+}
+
+// Edge test case to ensure a procedure holding a local string can not be
+// stored in a global dictionary either
+#[test]
+fn test_local_literal_in_procedure_invalidaccess()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    let result = postscript_interpreter.interpret("globaldict /p { { (local) } } put");
+    assert_eq!(result.unwrap_err().name, "invalidaccess");
+
+    // Procedures of names and numbers only are fine
+    postscript_interpreter.interpret("clear globaldict /q { 1 add } put").unwrap();
+}
+
+// Edge test case to ensure a save object stays invalid after its restore,
+// even once a new save is made in its place
+#[test]
+fn test_restore_stale_save()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("save globaldict exch /s exch put globaldict /s get restore save pop").unwrap();
+    let result = postscript_interpreter.interpret("globaldict /s get restore");
+
+    assert_eq!(result.unwrap_err().name, "invalidrestore");
+}
+
+// Normal test case to ensure restoring an outer save also undoes changes
+// only an inner save recorded
+#[test]
+fn test_restore_outer_save()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/x 1 def save save /x 2 def pop restore x").unwrap();
+
+    assert_eq!(postscript_interpreter.stack_listing(), "1");
+}
+
+// Normal test case to ensure restore rolls back objects only the running
+// procedure holds, such as the literals in its body
+#[test]
+fn test_restore_procedure_literal()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("2 { save (abc) dup 0 get exch 0 88 put exch restore } repeat").unwrap();
+
+    assert_eq!(postscript_interpreter.stack_listing(), "97\n97");
+}
+
+// Normal test case to ensure restore rolls back entries put into a
+// dictionary and changes to its access
+#[test]
+fn test_restore_dictionary_put()
+{
+    let mut postscript_interpreter = Interpreter::new(ScopeMode::Dynamic);
+
+    postscript_interpreter.interpret("/d 1 dict def save d /k 1 put d readonly pop restore d length d wcheck").unwrap();
+
+    assert_eq!(postscript_interpreter.stack_listing(), "true\n0");
+}